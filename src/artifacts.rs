@@ -0,0 +1,329 @@
+use std::path::PathBuf;
+
+use crate::entity::{FindBuilder, MatchInfo};
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::matcher::match_candidates;
+use crate::operation::OperationId;
+use crate::screenshot::Screenshot;
+use crate::zx_touch::ZxTouch;
+use crate::{debug, error};
+
+const REGION_COLOR: [u8; 3] = [0, 128, 255];
+const CANDIDATE_COLOR: [u8; 3] = [255, 200, 0];
+const TAP_COLOR: [u8; 3] = [255, 0, 0];
+
+/// 调试截图的保存位置和截图采样间隔
+#[derive(Debug, Clone)]
+pub struct DebugArtifacts {
+    pub dir: PathBuf,
+    pub step: u32,
+}
+
+unsafe impl Send for DebugArtifacts {}
+unsafe impl Sync for DebugArtifacts {}
+
+impl DebugArtifacts {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            step: 2,
+        }
+    }
+    pub fn step(&mut self, step: u32) -> &mut Self {
+        self.step = step;
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+/// 标注在调试截图上的内容,坐标为设备竖屏像素
+#[derive(Debug, Clone, Default)]
+pub struct Annotation {
+    /// 查找区域
+    pub region: Option<Rect>,
+    /// 最佳候选结果
+    pub candidate: Option<Rect>,
+    /// 点击位置
+    pub tap: Option<Point>,
+}
+
+unsafe impl Send for Annotation {}
+unsafe impl Sync for Annotation {}
+
+/// 在截图上画出查找区域、候选框和点击位置
+pub fn annotate(screenshot: &Screenshot, annotation: &Annotation) -> image::RgbImage {
+    let mut image = screenshot.to_image();
+    let step = screenshot.step.max(1) as f64;
+    let to_sample = |point: Point| {
+        (
+            ((point.x - screenshot.rect.x) / step).round() as i64,
+            ((point.y - screenshot.rect.y) / step).round() as i64,
+        )
+    };
+    let put = |image: &mut image::RgbImage, x: i64, y: i64, color: [u8; 3]| {
+        if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+            image.put_pixel(x as u32, y as u32, image::Rgb(color));
+        }
+    };
+    let rects = [
+        (annotation.region, REGION_COLOR),
+        (annotation.candidate, CANDIDATE_COLOR),
+    ];
+    for (rect, color) in rects {
+        let Some(rect) = rect else {
+            continue;
+        };
+        let (left, top) = to_sample(rect.origin());
+        let (right, bottom) = to_sample(Point::new(rect.right(), rect.bottom()));
+        for x in left..=right {
+            put(&mut image, x, top, color);
+            put(&mut image, x, bottom, color);
+        }
+        for y in top..=bottom {
+            put(&mut image, left, y, color);
+            put(&mut image, right, y, color);
+        }
+    }
+    if let Some(tap) = annotation.tap {
+        let (x, y) = to_sample(tap);
+        for offset in -4..=4 {
+            put(&mut image, x + offset, y, TAP_COLOR);
+            put(&mut image, x, y + offset, TAP_COLOR);
+        }
+    }
+    image
+}
+
+/// 查找失败时的标注。查找时没有候选(设备端查找没找到)而有模板时,在调试截图的查找区域内
+/// 查找模板,标出相似度最高的位置
+fn miss_annotation(
+    screenshot: &Screenshot,
+    region: Option<Rect>,
+    candidate: Option<&MatchInfo>,
+    template: Option<&image::RgbImage>,
+    find_builder: &FindBuilder,
+) -> Annotation {
+    let candidate = candidate.map(|candidate| candidate.rect).or_else(|| {
+        let searched = match region {
+            Some(region) => screenshot.crop(region, screenshot.step),
+            None => screenshot.clone(),
+        };
+        let best = match_candidates(&searched, template?, find_builder);
+        best.first().map(|info| info.rect)
+    });
+    Annotation {
+        region,
+        candidate,
+        tap: None,
+    }
+}
+
+/// 文件名:时间戳、操作编号、类型和模板名,模板名中的路径分隔符等替换为 `_`
+fn artifact_name(id: OperationId, kind: &str, label: &str, millis: u128) -> String {
+    let label = label.rsplit('/').next().unwrap_or(label);
+    let label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{}-{}-{}.png", millis, id, kind, label)
+}
+
+impl ZxTouch {
+    /// 开启后查找失败或按图点击时保存标注过的截图
+    pub fn set_debug_artifacts(&mut self, debug_artifacts: Option<DebugArtifacts>) {
+        self.debug_artifacts = debug_artifacts;
+    }
+    /// 查找区域换算成设备竖屏像素
+    pub(crate) async fn device_region(&self, find_builder: &FindBuilder) -> Option<Rect> {
+        let region = find_builder.region?;
+        let a = self.to_device(region.origin()).await.ok()?;
+        let b = self
+            .to_device(Point::new(region.right(), region.bottom()))
+            .await
+            .ok()?;
+        Some(Rect::from_corners(a, b))
+    }
+    /// 查找失败时保存截图,每个模板一张,标注查找区域和该模板的最佳候选。所有模板共用一次截图
+    pub(crate) async fn save_miss_artifacts(
+        &self,
+        id: OperationId,
+        find_builder: &FindBuilder,
+        misses: &[(&str, Option<MatchInfo>)],
+    ) {
+        if self.debug_artifacts.is_none() {
+            return;
+        }
+        let region = self.device_region(find_builder).await;
+        // 设备端查找没有给出候选,需要模板在调试截图上再找一次
+        let mut templates = Vec::with_capacity(misses.len());
+        for (label, candidate) in misses {
+            let template = match candidate {
+                Some(_) => None,
+                None => self.template_image(label).await.ok(),
+            };
+            templates.push(template);
+        }
+        self.save_debug_artifacts(id, "miss", |screenshot| {
+            misses
+                .iter()
+                .zip(&templates)
+                .map(|((label, candidate), template)| {
+                    let annotation = miss_annotation(
+                        screenshot,
+                        region,
+                        candidate.as_ref(),
+                        template.as_deref(),
+                        find_builder,
+                    );
+                    (*label, annotation)
+                })
+                .collect()
+        })
+        .await;
+    }
+    /// 按图点击前保存截图,标注查找区域、匹配结果和点击位置
+    pub(crate) async fn save_tap_artifact(
+        &self,
+        id: OperationId,
+        label: &str,
+        find_builder: &FindBuilder,
+        info: &MatchInfo,
+        tap: Point,
+    ) {
+        let corners = (
+            self.to_device(info.rect.origin()).await,
+            self.to_device(Point::new(info.rect.right(), info.rect.bottom()))
+                .await,
+            self.to_device(tap).await,
+        );
+        let (Ok(a), Ok(b), Ok(tap)) = corners else {
+            return;
+        };
+        let annotation = Annotation {
+            region: self.device_region(find_builder).await,
+            candidate: Some(Rect::from_corners(a, b)),
+            tap: Some(tap),
+        };
+        self.save_debug_artifacts(id, "tap", |_| vec![(label, annotation)])
+            .await;
+    }
+    /// 保存调试截图,未开启时什么也不做。`annotate_all` 根据截图给出每张图的名称和标注。
+    /// 保存失败只记录日志,不影响调用方
+    async fn save_debug_artifacts<'a>(
+        &self,
+        id: OperationId,
+        kind: &str,
+        annotate_all: impl FnOnce(&Screenshot) -> Vec<(&'a str, Annotation)>,
+    ) {
+        if let Err(_e) = self.try_save_debug_artifacts(id, kind, annotate_all).await {
+            error!("{} save debug screenshot error: {}", id, _e);
+        }
+    }
+    async fn try_save_debug_artifacts<'a>(
+        &self,
+        id: OperationId,
+        kind: &str,
+        annotate_all: impl FnOnce(&Screenshot) -> Vec<(&'a str, Annotation)>,
+    ) -> Result<(), Error> {
+        let Some(artifacts) = self.debug_artifacts.clone() else {
+            return Ok(());
+        };
+        let screenshot = self.screenshot(artifacts.step).await?;
+        std::fs::create_dir_all(&artifacts.dir)?;
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        for (label, annotation) in annotate_all(&screenshot) {
+            let path = artifacts.dir.join(artifact_name(id, kind, label, millis));
+            annotate(&screenshot, &annotation)
+                .save(&path)
+                .map_err(|e| Error::Err(e.to_string()))?;
+            debug!("{} saved debug screenshot: {:?}", id, path);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Color;
+
+    #[test]
+    fn test_artifact_name() {
+        assert_eq!(
+            artifact_name(OperationId(3), "miss", "/var/root/rust/find.jpg", 42),
+            "42-op3-miss-find_jpg.png"
+        );
+        assert_eq!(
+            artifact_name(OperationId(12), "tap", "login button", 7),
+            "7-op12-tap-login_button.png"
+        );
+    }
+
+    #[test]
+    fn test_miss_annotation() {
+        let template = image::RgbImage::from_fn(12, 12, |x, y| {
+            if x.abs_diff(6) <= 1 || y.abs_diff(6) <= 1 {
+                image::Rgb([250, 30, 30])
+            } else {
+                image::Rgb([40, 40, 200])
+            }
+        });
+        let mut screen = image::RgbImage::from_pixel(80, 60, image::Rgb([128, 128, 128]));
+        image::imageops::overlay(&mut screen, &template, 30, 20);
+        let screenshot = Screenshot::from_image(&screen, Rect::new(0, 0, 80, 60), 1);
+        // 阈值高到找不到,但仍然标出相似度最高的位置
+        let find_builder = FindBuilder::new().acceptable(1.1).max_try_times(1).build();
+        let region = Some(Rect::new(20, 10, 40, 40));
+        let annotation = miss_annotation(&screenshot, region, None, Some(&template), &find_builder);
+        assert_eq!(annotation.candidate, Some(Rect::new(30, 20, 12, 12)));
+        let image = annotate(&screenshot, &annotation);
+        assert_eq!(image.get_pixel(30, 20).0, CANDIDATE_COLOR);
+        assert_eq!(image.get_pixel(36, 32).0, CANDIDATE_COLOR);
+
+        // 查找时已经有候选就直接使用
+        let candidate = MatchInfo {
+            rect: Rect::new(5, 5, 10, 10),
+            score: Some(0.5),
+        };
+        let annotation = miss_annotation(&screenshot, None, Some(&candidate), None, &find_builder);
+        assert_eq!(annotation.candidate, Some(candidate.rect));
+        assert_eq!(
+            annotate(&screenshot, &annotation).get_pixel(5, 5).0,
+            CANDIDATE_COLOR
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let screenshot = Screenshot {
+            rect: Rect::new(0, 0, 40, 40),
+            step: 2,
+            width: 20,
+            height: 20,
+            pixels: vec![Color::new(10, 10, 10); 400],
+        };
+        let annotation = Annotation {
+            region: Some(Rect::new(0, 0, 20, 20)),
+            candidate: Some(Rect::new(20, 20, 10, 10)),
+            tap: Some(Point::new(25, 25)),
+        };
+        let image = annotate(&screenshot, &annotation);
+        assert_eq!(image.get_pixel(5, 0).0, REGION_COLOR);
+        assert_eq!(image.get_pixel(10, 11).0, CANDIDATE_COLOR);
+        assert_eq!(image.get_pixel(13, 11).0, TAP_COLOR);
+        assert_eq!(image.get_pixel(18, 3).0, [10, 10, 10]);
+    }
+}
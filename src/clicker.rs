@@ -0,0 +1,436 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::debug;
+use crate::entity::{Color, FindBuilder, TouchType};
+use crate::error::Error;
+use crate::finger::{clamp_to_screen, validate_coordinate};
+use crate::geometry::{Point, Size};
+use crate::random::Rng;
+use crate::zx_touch::ZxTouch;
+
+/// 用于从其他任务停止连点
+#[derive(Debug, Clone, Default)]
+pub struct StopToken(Arc<AtomicBool>);
+
+unsafe impl Send for StopToken {}
+unsafe impl Sync for StopToken {}
+
+impl StopToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// 连点的停止条件
+#[derive(Debug, Clone)]
+pub enum StopCondition {
+    /// 图片出现
+    ImageAppears(String, FindBuilder),
+    /// 该点的颜色与开始连点时相比,某个通道的差值超过 `tolerance`
+    ColorChanges { point: Point, tolerance: u8 },
+}
+
+unsafe impl Send for StopCondition {}
+unsafe impl Sync for StopCondition {}
+
+/// 连点停止的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Count,
+    Duration,
+    /// 满足了第几个停止条件
+    Condition(usize),
+    Cancelled,
+}
+
+unsafe impl Send for StopReason {}
+unsafe impl Sync for StopReason {}
+
+/// 连点参数,`count`、`duration`、停止条件和 `stop_token` 至少设置一个
+#[derive(Debug, Clone)]
+pub struct ClickBuilder {
+    /// 依次轮流点击的坐标
+    pub points: Vec<Point>,
+    /// 每秒点击次数
+    pub cps: f64,
+    pub count: Option<u32>,
+    /// 持续时间(毫秒)
+    pub duration: Option<u32>,
+    /// 点击位置的随机偏移半径(设备像素),偏移后限制在屏幕以内
+    pub jitter: f64,
+    /// 点击间隔的随机浮动比例,0.2 表示 ±20%
+    pub interval_jitter: f64,
+    pub conditions: Vec<StopCondition>,
+    /// 每点击多少次检查一次停止条件
+    pub check_every: u32,
+    pub stop_token: Option<StopToken>,
+    /// 随机数种子,默认使用当前时间
+    pub seed: Option<u64>,
+}
+
+unsafe impl Send for ClickBuilder {}
+unsafe impl Sync for ClickBuilder {}
+
+impl ClickBuilder {
+    pub fn new() -> Self {
+        Self {
+            points: Vec::new(),
+            cps: 10.0,
+            count: None,
+            duration: None,
+            jitter: 0.0,
+            interval_jitter: 0.0,
+            conditions: Vec::new(),
+            check_every: 10,
+            stop_token: None,
+            seed: None,
+        }
+    }
+    pub fn point(&mut self, point: impl Into<Point>) -> &mut Self {
+        self.points.push(point.into());
+        self
+    }
+    pub fn cps(&mut self, cps: f64) -> &mut Self {
+        self.cps = cps;
+        self
+    }
+    pub fn count(&mut self, count: u32) -> &mut Self {
+        self.count = Some(count);
+        self
+    }
+    pub fn duration(&mut self, duration: u32) -> &mut Self {
+        self.duration = Some(duration);
+        self
+    }
+    pub fn jitter(&mut self, jitter: f64) -> &mut Self {
+        self.jitter = jitter;
+        self
+    }
+    pub fn interval_jitter(&mut self, interval_jitter: f64) -> &mut Self {
+        self.interval_jitter = interval_jitter;
+        self
+    }
+    pub fn stop_when(&mut self, condition: StopCondition) -> &mut Self {
+        self.conditions.push(condition);
+        self
+    }
+    pub fn check_every(&mut self, check_every: u32) -> &mut Self {
+        self.check_every = check_every;
+        self
+    }
+    pub fn stop_token(&mut self, stop_token: StopToken) -> &mut Self {
+        self.stop_token = Some(stop_token);
+        self
+    }
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.points.is_empty() {
+            return Err(Error::Err(
+                "auto click needs at least one point".to_string(),
+            ));
+        }
+        if !self.cps.is_finite() || self.cps <= 0.0 {
+            return Err(Error::Err(format!("invalid cps: {}", self.cps)));
+        }
+        if self.count.is_none()
+            && self.duration.is_none()
+            && self.conditions.is_empty()
+            && self.stop_token.is_none()
+        {
+            return Err(Error::Err("auto click needs a stop condition".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ClickBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 连点结果统计,间隔单位为毫秒
+#[derive(Debug, Clone)]
+pub struct ClickStats {
+    pub clicks: u32,
+    pub elapsed: Duration,
+    pub target_cps: f64,
+    /// 第一次到最后一次点击之间实际达到的每秒点击次数
+    pub achieved_cps: f64,
+    pub mean_interval: f64,
+    pub max_interval: f64,
+    pub stop_reason: StopReason,
+}
+
+unsafe impl Send for ClickStats {}
+unsafe impl Sync for ClickStats {}
+
+/// 点击节奏:按目标间隔排下一次点击,落后超过一个间隔时不再补点
+struct Pacer {
+    interval: f64,
+    jitter: f64,
+}
+
+impl Pacer {
+    fn new(cps: f64, jitter: f64) -> Self {
+        Self {
+            interval: 1000.0 / cps,
+            jitter: jitter.clamp(0.0, 1.0),
+        }
+    }
+    fn next(&self, scheduled: Instant, now: Instant, rng: &mut Rng) -> Instant {
+        let interval = self.interval * (1.0 + self.jitter * rng.range(-1.0, 1.0));
+        let next = scheduled + Duration::from_secs_f64(interval / 1000.0);
+        let late = Duration::from_secs_f64(self.interval / 1000.0);
+        if next + late < now {
+            now
+        } else {
+            next
+        }
+    }
+}
+
+/// 记录点击时间
+#[derive(Default)]
+struct ClickTimes {
+    clicks: u32,
+    first: Option<Instant>,
+    last: Option<Instant>,
+    max_interval: Duration,
+}
+
+impl ClickTimes {
+    fn record(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            self.max_interval = self.max_interval.max(now.duration_since(last));
+        }
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+        self.clicks += 1;
+    }
+    fn stats(&self, start: Instant, cps: f64, stop_reason: StopReason) -> ClickStats {
+        let span = match (self.first, self.last) {
+            (Some(first), Some(last)) => last.duration_since(first).as_secs_f64() * 1000.0,
+            _ => 0.0,
+        };
+        let (achieved_cps, mean_interval) = if self.clicks > 1 && span > 0.0 {
+            let intervals = (self.clicks - 1) as f64;
+            (intervals * 1000.0 / span, span / intervals)
+        } else {
+            (0.0, 0.0)
+        };
+        ClickStats {
+            clicks: self.clicks,
+            elapsed: self.last.unwrap_or(start).duration_since(start),
+            target_cps: cps,
+            achieved_cps,
+            mean_interval,
+            max_interval: self.max_interval.as_secs_f64() * 1000.0,
+            stop_reason,
+        }
+    }
+}
+
+/// 设备像素坐标在 `radius` 以内随机偏移,限制在屏幕以内
+fn jitter_point(point: Point, radius: f64, screen: Size, rng: &mut Rng) -> Point {
+    if radius <= 0.0 {
+        return point;
+    }
+    let (dx, dy) = rng.in_circle(radius);
+    clamp_to_screen(point.offset(dx, dy), screen)
+}
+
+impl ZxTouch {
+    /// 连点器:按 `cps` 轮流点击各个坐标,直到达到次数、时长、停止条件或被取消
+    pub async fn auto_click(&self, builder: ClickBuilder) -> Result<ClickStats, Error> {
+        builder.validate()?;
+        let mut rng = builder.seed.map(Rng::new).unwrap_or_else(Rng::from_time);
+        let mut baselines: Vec<Option<Color>> = Vec::with_capacity(builder.conditions.len());
+        for condition in &builder.conditions {
+            baselines.push(match condition {
+                StopCondition::ColorChanges { point, .. } => Some(self.pick_color(*point).await?),
+                StopCondition::ImageAppears(..) => None,
+            });
+        }
+        // 点击位置提前换算成设备像素,偏移在设备像素下计算
+        let screen = self.device_screen_size().await?;
+        let mut points = Vec::with_capacity(builder.points.len());
+        for &point in &builder.points {
+            let point = self.to_device(point).await?;
+            validate_coordinate(point, Some(screen))?;
+            points.push(point);
+        }
+        let pacer = Pacer::new(builder.cps, builder.interval_jitter);
+        let start = Instant::now();
+        let deadline = builder
+            .duration
+            .map(|duration| start + Duration::from_millis(duration as u64));
+        let check_every = builder.check_every.max(1);
+        let mut times = ClickTimes::default();
+        let mut scheduled = start;
+        let stop_reason = loop {
+            if builder.stop_token.as_ref().is_some_and(|x| x.is_stopped()) {
+                break StopReason::Cancelled;
+            }
+            if builder.count.is_some_and(|count| times.clicks >= count) {
+                break StopReason::Count;
+            }
+            if deadline.is_some_and(|deadline| scheduled >= deadline) {
+                break StopReason::Duration;
+            }
+            if times.clicks % check_every == 0 {
+                if let Some(index) = self
+                    .matched_condition(&builder.conditions, &baselines)
+                    .await?
+                {
+                    break StopReason::Condition(index);
+                }
+            }
+            let now = Instant::now();
+            if scheduled > now {
+                futures_timer::Delay::new(scheduled - now).await;
+            }
+            let point = points[times.clicks as usize % points.len()];
+            self.click(jitter_point(point, builder.jitter, screen, &mut rng))
+                .await?;
+            let now = Instant::now();
+            times.record(now);
+            scheduled = pacer.next(scheduled, now, &mut rng);
+        };
+        let stats = times.stats(start, builder.cps, stop_reason);
+        debug!("auto click finished: {:?}", stats);
+        Ok(stats)
+    }
+    /// 在设备像素坐标按下后立即抬起。连点器自己控制偏移和节奏,不再叠加拟人化
+    async fn click(&self, point: Point) -> Result<(), Error> {
+        let finger = self.allocate_finger()?;
+        self.basetouch(TouchType::Down, point, finger.finger())
+            .await?;
+        finger.up().await
+    }
+    /// 返回第一个满足的停止条件的下标
+    async fn matched_condition(
+        &self,
+        conditions: &[StopCondition],
+        baselines: &[Option<Color>],
+    ) -> Result<Option<usize>, Error> {
+        for (index, condition) in conditions.iter().enumerate() {
+            let matched = match condition {
+                StopCondition::ImageAppears(image_path, find_builder) => self
+                    .locate_image(image_path, find_builder)
+                    .await?
+                    .0
+                    .is_some(),
+                StopCondition::ColorChanges { point, tolerance } => {
+                    let color = self.pick_color(*point).await?;
+                    baselines[index].is_some_and(|baseline| color.distance(baseline) > *tolerance)
+                }
+            };
+            if matched {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_builder_validate() {
+        assert!(ClickBuilder::new().count(10).validate().is_err());
+        assert!(ClickBuilder::new().point((1, 1)).validate().is_err());
+        assert!(ClickBuilder::new()
+            .point((1, 1))
+            .count(10)
+            .cps(0.0)
+            .validate()
+            .is_err());
+        assert!(ClickBuilder::new()
+            .point((1, 1))
+            .stop_token(StopToken::new())
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_pacer() {
+        let mut rng = Rng::new(1);
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let pacer = Pacer::new(20.0, 0.0);
+        assert_eq!(pacer.next(start, at(10), &mut rng), at(50));
+        assert_eq!(pacer.next(at(50), at(90), &mut rng), at(100));
+        assert_eq!(pacer.next(at(100), at(300), &mut rng), at(300));
+
+        let pacer = Pacer::new(10.0, 0.5);
+        for _ in 0..100 {
+            let next = pacer.next(start, start, &mut rng);
+            assert!(next >= at(50) && next <= at(150));
+        }
+    }
+
+    #[test]
+    fn test_click_stats() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut times = ClickTimes::default();
+        for millis in [10, 110, 160, 310] {
+            times.record(at(millis));
+        }
+        let stats = times.stats(start, 10.0, StopReason::Count);
+        assert_eq!(stats.clicks, 4);
+        assert_eq!(stats.elapsed, Duration::from_millis(310));
+        assert!((stats.achieved_cps - 10.0).abs() < 1e-6);
+        assert!((stats.mean_interval - 100.0).abs() < 1e-6);
+        assert!((stats.max_interval - 150.0).abs() < 1e-6);
+        assert_eq!(stats.stop_reason, StopReason::Count);
+
+        let stats = ClickTimes::default().stats(start, 10.0, StopReason::Cancelled);
+        assert_eq!((stats.clicks, stats.achieved_cps), (0, 0.0));
+    }
+
+    #[test]
+    fn test_jitter_point() {
+        let mut rng = Rng::new(3);
+        let screen = Size::new(100.0, 200.0);
+        assert_eq!(
+            jitter_point(Point::new(10.0, 10.0), 0.0, screen, &mut rng),
+            Point::new(10.0, 10.0)
+        );
+        for point in [Point::new(0.0, 0.0), Point::new(99.9, 199.9)] {
+            for _ in 0..100 {
+                let jittered = jitter_point(point, 5.0, screen, &mut rng);
+                assert!(validate_coordinate(jittered, Some(screen)).is_ok());
+                assert!(jittered.distance(point) <= 5.0 + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stop_token() {
+        let token = StopToken::new();
+        let other = token.clone();
+        assert!(!token.is_stopped());
+        other.stop();
+        assert!(token.is_stopped());
+    }
+}
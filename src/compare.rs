@@ -0,0 +1,252 @@
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::screenshot::Screenshot;
+use crate::zx_touch::ZxTouch;
+
+/// 截图比较参数
+#[derive(Debug, Clone)]
+pub struct CompareBuilder {
+    /// 单个像素各通道允许的最大差值
+    pub tolerance: u8,
+    /// 相似度不低于该值视为一致
+    pub threshold: f32,
+    /// 忽略的区域,如时间、动画。与 `screenshot_region` 的区域一样使用当前坐标系
+    pub ignore: Vec<Rect>,
+}
+
+unsafe impl Send for CompareBuilder {}
+unsafe impl Sync for CompareBuilder {}
+
+impl CompareBuilder {
+    pub fn new() -> Self {
+        Self {
+            tolerance: 8,
+            threshold: 0.99,
+            ignore: Vec::new(),
+        }
+    }
+    pub fn tolerance(&mut self, tolerance: u8) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+    pub fn threshold(&mut self, threshold: f32) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+    pub fn ignore(&mut self, rect: Rect) -> &mut Self {
+        self.ignore.push(rect);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for CompareBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 截图比较结果
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// 一致的采样点占参与比较的采样点的比例
+    pub similarity: f32,
+    pub passed: bool,
+    /// 不一致的采样点数
+    pub differing: usize,
+    /// 参与比较(未被忽略)的采样点数
+    pub compared: usize,
+    /// 所有不一致采样点的外接矩形(设备竖屏像素)
+    pub bounds: Option<Rect>,
+    /// 按采样点存放的比较结果,`true` 表示不一致
+    pub mask: Vec<bool>,
+    pub width: u32,
+    pub height: u32,
+}
+
+unsafe impl Send for Comparison {}
+unsafe impl Sync for Comparison {}
+
+/// 比较两张截图,两者的区域和采样间隔必须相同。
+/// 截图使用设备竖屏像素,`ignore` 也按设备竖屏像素处理;`compare_screen` 会先把它换算过来
+pub fn compare(
+    actual: &Screenshot,
+    baseline: &Screenshot,
+    compare_builder: &CompareBuilder,
+) -> Result<Comparison, Error> {
+    if actual.width != baseline.width || actual.height != baseline.height {
+        return Err(Error::Err(format!(
+            "screenshot size {}x{} differs from baseline {}x{}",
+            actual.width, actual.height, baseline.width, baseline.height
+        )));
+    }
+    let step = actual.step.max(1) as f64;
+    let (mut compared, mut differing) = (0, 0);
+    let mut bounds: Option<(Point, Point)> = None;
+    let mut mask = vec![false; actual.pixels.len()];
+    for (index, (a, b)) in actual.pixels.iter().zip(&baseline.pixels).enumerate() {
+        let column = index as u32 % actual.width;
+        let row = index as u32 / actual.width;
+        let point = Point::new(
+            actual.rect.x + column as f64 * step,
+            actual.rect.y + row as f64 * step,
+        );
+        if compare_builder
+            .ignore
+            .iter()
+            .any(|rect| rect.contains(point))
+        {
+            continue;
+        }
+        compared += 1;
+        if a.distance(*b) <= compare_builder.tolerance {
+            continue;
+        }
+        differing += 1;
+        mask[index] = true;
+        let end = point.offset(step, step);
+        bounds = Some(match bounds {
+            Some((min, max)) => (
+                Point::new(min.x.min(point.x), min.y.min(point.y)),
+                Point::new(max.x.max(end.x), max.y.max(end.y)),
+            ),
+            None => (point, end),
+        });
+    }
+    let similarity = if compared == 0 {
+        1.0
+    } else {
+        (compared - differing) as f32 / compared as f32
+    };
+    Ok(Comparison {
+        similarity,
+        passed: similarity >= compare_builder.threshold,
+        differing,
+        compared,
+        bounds: bounds.map(|(min, max)| Rect::from_corners(min, max)),
+        mask,
+        width: actual.width,
+        height: actual.height,
+    })
+}
+
+#[cfg(feature = "image")]
+impl Comparison {
+    /// 差异图:不一致的采样点标红,其余变暗
+    pub fn diff_image(&self, actual: &Screenshot) -> image::RgbImage {
+        image::RgbImage::from_fn(self.width, self.height, |column, row| {
+            let index = (row * self.width + column) as usize;
+            if self.mask[index] {
+                return image::Rgb([255, 0, 0]);
+            }
+            let color = actual.get(column, row).unwrap_or_default();
+            image::Rgb([color.r / 3, color.g / 3, color.b / 3])
+        })
+    }
+    pub fn save_diff(
+        &self,
+        actual: &Screenshot,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), Error> {
+        self.diff_image(actual)
+            .save(path)
+            .map_err(|e| Error::Err(e.to_string()))
+    }
+}
+
+impl ZxTouch {
+    /// 按基准截图的区域和采样间隔截图并与基准比较,`ignore` 的区域从当前坐标系换算成设备像素
+    pub async fn compare_screen(
+        &self,
+        baseline: &Screenshot,
+        compare_builder: CompareBuilder,
+    ) -> Result<(Screenshot, Comparison), Error> {
+        let mut device_builder = compare_builder;
+        for rect in device_builder.ignore.iter_mut() {
+            *rect = self.to_device_rect(*rect).await?;
+        }
+        let actual = self.device_screenshot(baseline.rect, baseline.step).await?;
+        let comparison = compare(&actual, baseline, &device_builder)?;
+        Ok((actual, comparison))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Color;
+
+    fn screenshot(pixels: Vec<Color>) -> Screenshot {
+        Screenshot {
+            rect: Rect::new(0, 0, 40, 20),
+            step: 10,
+            width: 4,
+            height: 2,
+            pixels,
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = screenshot(vec![Color::new(100, 100, 100); 8]);
+        let mut actual = baseline.clone();
+        actual.pixels[1] = Color::new(104, 100, 100);
+        actual.pixels[6] = Color::new(0, 0, 0);
+        let result = compare(&actual, &baseline, &CompareBuilder::new().build()).unwrap();
+        assert_eq!((result.differing, result.compared), (1, 8));
+        assert_eq!(result.similarity, 0.875);
+        assert!(!result.passed);
+        assert_eq!(result.bounds, Some(Rect::new(20, 10, 10, 10)));
+
+        let ignored = CompareBuilder::new()
+            .ignore(Rect::new(15, 5, 20, 20))
+            .build();
+        let result = compare(&actual, &baseline, &ignored).unwrap();
+        assert_eq!((result.differing, result.compared), (0, 6));
+        assert!(result.passed);
+        assert_eq!(result.bounds, None);
+    }
+
+    #[test]
+    fn test_saved_baseline_geometry() {
+        // 与 `compare_screen` 相同:按加载的基准截图的区域和采样间隔从全屏截图中取出对比区域
+        let full = Screenshot {
+            rect: Rect::new(0, 0, 60, 50),
+            step: 1,
+            width: 60,
+            height: 50,
+            pixels: (0..3000)
+                .map(|index| Color::new((index % 60) as u8, (index / 60) as u8, 0))
+                .collect(),
+        };
+        let baseline = full.crop(Rect::new(20, 10, 30, 30), 5);
+        let path =
+            std::env::temp_dir().join(format!("zxtouch-baseline-{}.png", std::process::id()));
+        baseline.save(&path).unwrap();
+        let loaded = Screenshot::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, baseline);
+        let actual = full.crop(loaded.rect, loaded.step);
+        let result = compare(&actual, &loaded, &CompareBuilder::new()).unwrap();
+        assert_eq!(result.similarity, 1.0);
+        // 丢失区域时会按原点对比,结果不一致
+        let origin = Rect::from_origin_size(Point::default(), loaded.rect.size());
+        let actual = full.crop(origin, loaded.step);
+        assert!(
+            !compare(&actual, &loaded, &CompareBuilder::new())
+                .unwrap()
+                .passed
+        );
+    }
+
+    #[test]
+    fn test_compare_size_mismatch() {
+        let baseline = screenshot(vec![Color::default(); 8]);
+        let mut actual = baseline.clone();
+        actual.width = 2;
+        assert!(compare(&actual, &baseline, &CompareBuilder::new()).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use crate::entity::ScreenOrientation;
+use crate::error::Error;
+use crate::geometry::{Point, Rect, Size};
+use crate::zx_touch::ZxTouch;
+
+/// 调用方使用的坐标系,发送前统一换算成设备像素
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoordinateSpace {
+    /// 设备像素(默认)
+    Pixel,
+    /// 0.0~1.0 的比例坐标,两端都包含
+    Normalized,
+    /// 逻辑点,像素 = 点 × `get_screen_scale`
+    Logical,
+    /// 按设计稿分辨率编写的坐标,按屏幕大小等比换算
+    Design(Size),
+}
+
+unsafe impl Send for CoordinateSpace {}
+unsafe impl Sync for CoordinateSpace {}
+
+/// 当前连接设备的屏幕参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenMetrics {
+    pub size: Size,
+    pub scale: f64,
+}
+
+unsafe impl Send for ScreenMetrics {}
+unsafe impl Sync for ScreenMetrics {}
+
+impl ScreenMetrics {
+    /// 按显示方向看到的屏幕,横屏时宽高互换
+    pub fn oriented(&self, orientation: ScreenOrientation) -> ScreenMetrics {
+        if orientation.is_landscape() {
+            ScreenMetrics {
+                size: Size::new(self.size.height, self.size.width),
+                scale: self.scale,
+            }
+        } else {
+            *self
+        }
+    }
+}
+
+/// 显示方向与竖屏坐标一致,无需转换
+fn is_upright(orientation: ScreenOrientation) -> bool {
+    matches!(
+        orientation,
+        ScreenOrientation::Down | ScreenOrientation::Unknown(_)
+    )
+}
+
+/// 坐标精度,屏幕范围为 `[0, extent)`,边缘最多取到 `extent - EDGE`
+const EDGE: f64 = 0.1;
+
+/// 沿 `extent` 翻转坐标。`[0, extent)` 翻转后是 `(0, extent]`,0 会落到屏幕外,所以截到屏幕边缘以内
+fn flip(value: f64, extent: f64) -> f64 {
+    (extent - value).min(extent - EDGE)
+}
+
+/// 落在右下边缘上的坐标截到屏幕以内,超出屏幕的保持不变,留给发送前的检查
+fn inclusive(value: f64, extent: f64) -> f64 {
+    if value <= extent {
+        value.min(extent - EDGE)
+    } else {
+        value
+    }
+}
+
+/// 把当前显示方向下的像素坐标转换成竖屏坐标。
+/// `Left` 为 Home 键在左侧的横屏,`Right` 为 Home 键在右侧的横屏,无法识别的方向按竖屏处理
+pub fn visual_to_raw(
+    orientation: ScreenOrientation,
+    point: Point,
+    screen: &ScreenMetrics,
+) -> Point {
+    let Size { width, height } = screen.size;
+    match orientation {
+        ScreenOrientation::Down | ScreenOrientation::Unknown(_) => point,
+        ScreenOrientation::Up => Point::new(flip(point.x, width), flip(point.y, height)),
+        ScreenOrientation::Left => Point::new(point.y, flip(point.x, height)),
+        ScreenOrientation::Right => Point::new(flip(point.y, width), point.x),
+    }
+}
+
+/// `visual_to_raw` 的逆变换
+pub fn raw_to_visual(
+    orientation: ScreenOrientation,
+    point: Point,
+    screen: &ScreenMetrics,
+) -> Point {
+    let Size { width, height } = screen.size;
+    match orientation {
+        ScreenOrientation::Down | ScreenOrientation::Unknown(_) => point,
+        ScreenOrientation::Up => Point::new(flip(point.x, width), flip(point.y, height)),
+        ScreenOrientation::Left => Point::new(flip(point.y, height), point.x),
+        ScreenOrientation::Right => Point::new(point.y, flip(point.x, width)),
+    }
+}
+
+impl CoordinateSpace {
+    /// 每个单位对应的设备像素
+    fn factors(&self, screen: &ScreenMetrics) -> (f64, f64) {
+        match *self {
+            CoordinateSpace::Pixel => (1.0, 1.0),
+            CoordinateSpace::Normalized => (screen.size.width, screen.size.height),
+            CoordinateSpace::Logical => (screen.scale, screen.scale),
+            CoordinateSpace::Design(design) => (
+                screen.size.width / design.width,
+                screen.size.height / design.height,
+            ),
+        }
+    }
+    /// 换算成设备像素。除 `Pixel` 外屏幕范围包含右下边缘(如 `Normalized` 的 1.0),换算后截到屏幕以内
+    pub fn to_device(&self, point: Point, screen: &ScreenMetrics) -> Point {
+        let (fx, fy) = self.factors(screen);
+        let point = point.scale(fx, fy);
+        if *self == CoordinateSpace::Pixel {
+            return point;
+        }
+        Point::new(
+            inclusive(point.x, screen.size.width),
+            inclusive(point.y, screen.size.height),
+        )
+    }
+    /// 从设备像素换算回当前坐标系
+    pub fn from_device(&self, point: Point, screen: &ScreenMetrics) -> Point {
+        let (fx, fy) = self.factors(screen);
+        point.scale(1.0 / fx, 1.0 / fy)
+    }
+}
+
+impl ZxTouch {
+    /// 屏幕大小和缩放比例,每个连接只向设备查询一次
+    pub async fn screen_metrics(&self) -> Result<ScreenMetrics, Error> {
+        let size = match self.cached_screen_size() {
+            Some(size) => size,
+            None => self.get_screen_size().await?,
+        };
+        let scale = match self.cached_screen_scale() {
+            Some(scale) => scale,
+            None => self.get_screen_scale().await?,
+        };
+        Ok(ScreenMetrics {
+            size,
+            scale: scale as f64,
+        })
+    }
+    /// 重新获取屏幕方向,旋转屏幕后调用
+    pub async fn refresh_orientation(&self) -> Result<ScreenOrientation, Error> {
+        self.get_screen_orientation().await
+    }
+    /// 坐标换算使用的屏幕方向,未开启方向感知时始终为竖屏
+    async fn effective_orientation(&self) -> Result<ScreenOrientation, Error> {
+        if !self.orientation_aware() {
+            return Ok(ScreenOrientation::Down);
+        }
+        match self.cached_screen_orientation() {
+            Some(orientation) => Ok(orientation),
+            None => self.refresh_orientation().await,
+        }
+    }
+    /// 把当前坐标系下的坐标换算成设备竖屏像素
+    pub async fn to_device(&self, point: Point) -> Result<Point, Error> {
+        let space = self.coordinate_space();
+        let orientation = self.effective_orientation().await?;
+        if space == CoordinateSpace::Pixel && is_upright(orientation) {
+            return Ok(point);
+        }
+        let screen = self.screen_metrics().await?;
+        let point = space.to_device(point, &screen.oriented(orientation));
+        Ok(visual_to_raw(orientation, point, &screen))
+    }
+    /// 把设备竖屏像素换算回当前坐标系
+    pub async fn from_device(&self, point: Point) -> Result<Point, Error> {
+        let space = self.coordinate_space();
+        let orientation = self.effective_orientation().await?;
+        if space == CoordinateSpace::Pixel && is_upright(orientation) {
+            return Ok(point);
+        }
+        let screen = self.screen_metrics().await?;
+        let point = raw_to_visual(orientation, point, &screen);
+        Ok(space.from_device(point, &screen.oriented(orientation)))
+    }
+    /// 把当前坐标系下的矩形换算成设备竖屏像素
+    pub async fn to_device_rect(&self, rect: Rect) -> Result<Rect, Error> {
+        let a = self.to_device(rect.origin()).await?;
+        let b = self
+            .to_device(Point::new(rect.right(), rect.bottom()))
+            .await?;
+        Ok(Rect::from_corners(a, b))
+    }
+    /// 换算设备竖屏像素下的矩形
+    pub async fn from_device_rect(&self, rect: Rect) -> Result<Rect, Error> {
+        let a = self.from_device(rect.origin()).await?;
+        let b = self
+            .from_device(Point::new(rect.right(), rect.bottom()))
+            .await?;
+        Ok(Rect::from_corners(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finger::validate_coordinate;
+
+    const SCREEN: ScreenMetrics = ScreenMetrics {
+        size: Size {
+            width: 1170.0,
+            height: 2532.0,
+        },
+        scale: 3.0,
+    };
+
+    #[test]
+    fn test_to_device() {
+        assert_eq!(
+            CoordinateSpace::Pixel.to_device(Point::new(10, 20), &SCREEN),
+            Point::new(10, 20)
+        );
+        assert_eq!(
+            CoordinateSpace::Normalized.to_device(Point::new(0.5, 0.25), &SCREEN),
+            Point::new(585, 633)
+        );
+        assert_eq!(
+            CoordinateSpace::Logical.to_device(Point::new(195, 422), &SCREEN),
+            Point::new(585, 1266)
+        );
+        let design = CoordinateSpace::Design(Size::new(750, 1334));
+        assert_eq!(
+            design.to_device(Point::new(375, 1334), &SCREEN),
+            Point::new(585, 2531.9)
+        );
+    }
+
+    #[test]
+    fn test_to_device_edge() {
+        let corner = CoordinateSpace::Normalized.to_device(Point::new(1.0, 1.0), &SCREEN);
+        assert_eq!(corner, Point::new(1169.9, 2531.9));
+        assert!(validate_coordinate(corner, Some(SCREEN.size)).is_ok());
+        let outside = CoordinateSpace::Normalized.to_device(Point::new(1.5, 0.0), &SCREEN);
+        assert_eq!(outside, Point::new(1755, 0));
+        assert!(validate_coordinate(outside, Some(SCREEN.size)).is_err());
+    }
+
+    #[test]
+    fn test_orientation_corners() {
+        // 横屏/倒屏下显示坐标的左上角在竖屏坐标中的位置
+        let origin = |orientation| visual_to_raw(orientation, Point::default(), &SCREEN);
+        assert_eq!(origin(ScreenOrientation::Down), Point::new(0, 0));
+        assert_eq!(origin(ScreenOrientation::Up), Point::new(1169.9, 2531.9));
+        assert_eq!(origin(ScreenOrientation::Left), Point::new(0, 2531.9));
+        assert_eq!(origin(ScreenOrientation::Right), Point::new(1169.9, 0));
+        let visual = SCREEN.oriented(ScreenOrientation::Right);
+        assert_eq!(visual.size, Size::new(2532, 1170));
+        let far = visual_to_raw(
+            ScreenOrientation::Right,
+            Point::new(2531.9, 1169.9),
+            &SCREEN,
+        );
+        assert!((far.x - 0.1).abs() < 1e-9);
+        assert!((far.y - 2531.9).abs() < 1e-9);
+        for orientation in [
+            ScreenOrientation::Down,
+            ScreenOrientation::Up,
+            ScreenOrientation::Left,
+            ScreenOrientation::Right,
+        ] {
+            assert!(validate_coordinate(origin(orientation), Some(SCREEN.size)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_orientation_round_trip() {
+        let orientations = [
+            ScreenOrientation::Down,
+            ScreenOrientation::Up,
+            ScreenOrientation::Left,
+            ScreenOrientation::Right,
+        ];
+        let point = Point::new(100, 200);
+        for orientation in orientations {
+            let raw = visual_to_raw(orientation, point, &SCREEN);
+            assert_eq!(
+                raw_to_visual(orientation, raw, &SCREEN),
+                point,
+                "{:?}",
+                orientation
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let spaces = [
+            CoordinateSpace::Pixel,
+            CoordinateSpace::Normalized,
+            CoordinateSpace::Logical,
+            CoordinateSpace::Design(Size::new(390, 844)),
+        ];
+        let point = Point::new(0.3, 0.7);
+        for space in spaces {
+            let back = space.from_device(space.to_device(point, &SCREEN), &SCREEN);
+            assert!(back.distance(point) < 1e-9, "{:?}", space);
+        }
+    }
+}
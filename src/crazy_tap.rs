@@ -0,0 +1,70 @@
+use crate::error::Error;
+use crate::finger::validate_coordinate;
+use crate::geometry::Point;
+use crate::r#type::MessageType;
+use crate::zx_touch::ZxTouch;
+
+/// 连点消息,参数为 `1;;x;;y;;间隔微秒`,停止为 `0`。
+/// 插件没有公开这条消息的参数,这里沿用 `PerformTouch` 的坐标(乘以 10 取整)和 `Usleep` 的时间单位,
+/// 在设备上确认之前只在 `experimental` feature 下提供
+fn crazy_tap_message(point: Option<Point>, interval: u32) -> String {
+    let message_type: u8 = MessageType::CrazyTap.into();
+    match point {
+        Some(point) => format!(
+            "{}1;;{};;{};;{}\r\n",
+            message_type,
+            (point.x * 10.0).round() as u32,
+            (point.y * 10.0).round() as u32,
+            interval as u64 * 1000
+        ),
+        None => format!("{}0\r\n", message_type),
+    }
+}
+
+impl ZxTouch {
+    /// 开始设备端连点,`interval` 为点击间隔(毫秒)。连点在设备上执行,不占用连接。
+    /// 插件不保证回复,所以只发送不等待结果
+    pub async fn start_crazy_tap(
+        &self,
+        point: impl Into<Point>,
+        interval: u32,
+    ) -> Result<(), Error> {
+        if interval == 0 {
+            return Err(Error::InvalidTouch(
+                "crazy tap interval must be positive".to_string(),
+            ));
+        }
+        let point = self.to_device(point.into()).await?;
+        validate_coordinate(point, Some(self.device_screen_size().await?))?;
+        self.send(&crazy_tap_message(Some(point), interval)).await
+    }
+    /// 停止设备端连点
+    pub async fn stop_crazy_tap(&self) -> Result<(), Error> {
+        self.send(&crazy_tap_message(None, 0)).await
+    }
+    /// 设备端连点 `duration` 毫秒后停止,等待期间连接可以被其他任务使用
+    pub async fn crazy_tap_for(
+        &self,
+        point: impl Into<Point>,
+        interval: u32,
+        duration: u32,
+    ) -> Result<(), Error> {
+        self.start_crazy_tap(point, interval).await?;
+        self.delay(duration).await;
+        self.stop_crazy_tap().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crazy_tap_message() {
+        assert_eq!(
+            crazy_tap_message(Some(Point::new(100.4, 200.6)), 50),
+            "161;;1004;;2006;;50000\r\n"
+        );
+        assert_eq!(crazy_tap_message(None, 0), "160\r\n");
+    }
+}
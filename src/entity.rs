@@ -1,4 +1,7 @@
-#[derive(Debug, Clone, Copy)]
+use crate::geometry::{Point, Rect};
+use crate::gesture::Easing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TouchFinger {
     One,
     Two,
@@ -7,7 +10,7 @@ pub enum TouchFinger {
     Five,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TouchType {
     Down,
     Move,
@@ -22,6 +25,21 @@ unsafe impl Send for TouchFinger {}
 
 unsafe impl Sync for TouchFinger {}
 
+impl TouchFinger {
+    pub const ALL: [TouchFinger; 5] = [
+        TouchFinger::One,
+        TouchFinger::Two,
+        TouchFinger::Three,
+        TouchFinger::Four,
+        TouchFinger::Five,
+    ];
+
+    pub(crate) fn index(self) -> usize {
+        let value: u8 = self.into();
+        value as usize - 1
+    }
+}
+
 impl Into<u8> for TouchType {
     fn into(self) -> u8 {
         match self {
@@ -52,12 +70,47 @@ pub enum ParamType {
     U8(u8),
 }
 
-#[derive(Debug, Clone)]
+/// 屏幕方向,取值与 UIKit 的 `UIInterfaceOrientation` 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScreenOrientation {
-    Up = 2,    //倒屏
-    Down = 1,  //竖屏
-    Left = 3,  //左横屏
-    Right = 4, //右横屏
+    Up,           //倒屏 UIInterfaceOrientationPortraitUpsideDown = 2
+    Down,         //竖屏 UIInterfaceOrientationPortrait = 1
+    Left,         //左横屏(Home 键在左) UIInterfaceOrientationLandscapeLeft = 4
+    Right,        //右横屏(Home 键在右) UIInterfaceOrientationLandscapeRight = 3
+    Unknown(i32), //UIInterfaceOrientationUnknown = 0 或其他无法识别的值
+}
+
+impl ScreenOrientation {
+    pub fn is_portrait(&self) -> bool {
+        matches!(self, ScreenOrientation::Up | ScreenOrientation::Down)
+    }
+    pub fn is_landscape(&self) -> bool {
+        matches!(self, ScreenOrientation::Left | ScreenOrientation::Right)
+    }
+}
+
+/// RGB 颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+unsafe impl Send for Color {}
+unsafe impl Sync for Color {}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+    /// 各通道差值的最大值
+    pub fn distance(&self, other: Color) -> u8 {
+        self.r
+            .abs_diff(other.r)
+            .max(self.g.abs_diff(other.g))
+            .max(self.b.abs_diff(other.b))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -74,6 +127,9 @@ pub struct FindBuilder {
     pub acceptable: f32,
     pub max_try_times: u8,
     pub scale_ration: f32,
+    /// 查找区域,坐标与当前坐标系一致。开启 `image` feature 时只截取该区域在主机上查找;
+    /// 否则在设备上全屏查找,只接受中心点落在该区域内的结果
+    pub region: Option<Rect>,
 }
 
 unsafe impl Send for FindBuilder {}
@@ -94,12 +150,160 @@ impl TouchBuilder {
 }
 unsafe impl Send for TouchBuilder {}
 unsafe impl Sync for TouchBuilder {}
+
+/// 双指手势参数(捏合、旋转、平移)
+#[derive(Debug, Clone)]
+pub struct GestureBuilder {
+    pub center: Point,
+    /// 两指到中心点的起始/结束距离
+    pub start_radius: f64,
+    pub end_radius: f64,
+    /// 两指连线的起始/结束角度(度),顺时针为正
+    pub start_angle: f32,
+    pub end_angle: f32,
+    /// 中心点在手势过程中的位移
+    pub offset: Point,
+    /// 手势时长(毫秒)
+    pub duration: u32,
+    /// 插值步数
+    pub steps: u32,
+    pub easing: Easing,
+}
+
+unsafe impl Send for GestureBuilder {}
+unsafe impl Sync for GestureBuilder {}
+
+impl GestureBuilder {
+    pub fn new() -> Self {
+        Self {
+            center: Point::default(),
+            start_radius: 100.0,
+            end_radius: 100.0,
+            start_angle: 0.0,
+            end_angle: 0.0,
+            offset: Point::default(),
+            duration: 300,
+            steps: 10,
+            easing: Easing::Linear,
+        }
+    }
+    pub fn center(&mut self, center: impl Into<Point>) -> &mut Self {
+        self.center = center.into();
+        self
+    }
+    pub fn radius(
+        &mut self,
+        start_radius: impl Into<f64>,
+        end_radius: impl Into<f64>,
+    ) -> &mut Self {
+        self.start_radius = start_radius.into();
+        self.end_radius = end_radius.into();
+        self
+    }
+    pub fn angle(&mut self, start_angle: f32, end_angle: f32) -> &mut Self {
+        self.start_angle = start_angle;
+        self.end_angle = end_angle;
+        self
+    }
+    pub fn offset(&mut self, offset: impl Into<Point>) -> &mut Self {
+        self.offset = offset.into();
+        self
+    }
+    pub fn duration(&mut self, duration: u32) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+    pub fn steps(&mut self, steps: u32) -> &mut Self {
+        self.steps = steps;
+        self
+    }
+    pub fn easing(&mut self, easing: Easing) -> &mut Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for GestureBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 滑动参数
+#[derive(Debug, Clone)]
+pub struct SwipeBuilder {
+    pub from: Point,
+    pub to: Point,
+    /// 滑动时长(毫秒)
+    pub duration: u32,
+    /// 每秒发送的移动事件数
+    pub sample_rate: u32,
+    pub easing: Easing,
+    /// 甩动模式:抬起时的速度(像素/秒),设置后忽略 `easing`
+    pub fling: Option<f32>,
+}
+
+unsafe impl Send for SwipeBuilder {}
+unsafe impl Sync for SwipeBuilder {}
+
+impl SwipeBuilder {
+    pub fn new() -> Self {
+        Self {
+            from: Point::default(),
+            to: Point::default(),
+            duration: 300,
+            sample_rate: 60,
+            easing: Easing::EaseInOut,
+            fling: None,
+        }
+    }
+    pub fn from(&mut self, from: impl Into<Point>) -> &mut Self {
+        self.from = from.into();
+        self
+    }
+    pub fn to(&mut self, to: impl Into<Point>) -> &mut Self {
+        self.to = to.into();
+        self
+    }
+    pub fn duration(&mut self, duration: u32) -> &mut Self {
+        self.duration = duration;
+        self
+    }
+    pub fn sample_rate(&mut self, sample_rate: u32) -> &mut Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+    pub fn easing(&mut self, easing: Easing) -> &mut Self {
+        self.easing = easing;
+        self
+    }
+    pub fn fling(&mut self, end_velocity: f32) -> &mut Self {
+        self.fling = Some(end_velocity);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for SwipeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FindBuilder {
     pub fn new() -> Self {
         Self {
             acceptable: 0.8,
             max_try_times: 4,
             scale_ration: 0.8,
+            region: None,
         }
     }
     pub fn acceptable(&mut self, acceptable: f32) -> &mut Self {
@@ -114,38 +318,79 @@ impl FindBuilder {
         self.scale_ration = scale_ration;
         self
     }
+    pub fn region(&mut self, region: Rect) -> &mut Self {
+        self.region = Some(region);
+        self
+    }
 
     pub fn build(&self) -> Self {
         Self {
             acceptable: self.acceptable,
             max_try_times: self.max_try_times,
             scale_ration: self.scale_ration,
+            region: self.region,
         }
     }
+    /// 结果是否位于查找区域内,未设置区域时总是接受
+    pub fn accepts(&self, info: &MatchInfo) -> bool {
+        self.region
+            .is_none_or(|region| region.contains(info.center()))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct MatchInfo {
-    pub x: u32,
-    pub y: u32,
-    pub w: u32,
-    pub h: u32,
+    pub rect: Rect,
+    /// 相似度,设备端查找不返回相似度时为 `None`
+    pub score: Option<f32>,
+}
+
+impl MatchInfo {
+    pub fn center(&self) -> Point {
+        self.rect.center()
+    }
 }
 
 unsafe impl Send for MatchInfo {}
 unsafe impl Sync for MatchInfo {}
 
+/// 识别出的一段文字
+#[cfg(feature = "experimental")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextInfo {
+    pub text: String,
+    /// 文字所在的区域,插件没有返回位置时为 `None`
+    pub rect: Option<Rect>,
+}
+
+#[cfg(feature = "experimental")]
+unsafe impl Send for TextInfo {}
+#[cfg(feature = "experimental")]
+unsafe impl Sync for TextInfo {}
+
 unsafe impl Send for DeviceInfo {}
 unsafe impl Sync for DeviceInfo {}
 
 impl From<i32> for ScreenOrientation {
     fn from(value: i32) -> Self {
         match value {
-            0 => ScreenOrientation::Up,
             1 => ScreenOrientation::Down,
-            2 => ScreenOrientation::Left,
+            2 => ScreenOrientation::Up,
             3 => ScreenOrientation::Right,
-            _ => ScreenOrientation::Down,
+            4 => ScreenOrientation::Left,
+            _ => ScreenOrientation::Unknown(value),
+        }
+    }
+}
+
+impl From<ScreenOrientation> for i32 {
+    fn from(value: ScreenOrientation) -> Self {
+        match value {
+            ScreenOrientation::Down => 1,
+            ScreenOrientation::Up => 2,
+            ScreenOrientation::Right => 3,
+            ScreenOrientation::Left => 4,
+            ScreenOrientation::Unknown(value) => value,
         }
     }
 }
@@ -187,3 +432,43 @@ impl From<u8> for ParamType {
         ParamType::U8(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_orientation_from_i32() {
+        assert_eq!(ScreenOrientation::from(0), ScreenOrientation::Unknown(0));
+        assert_eq!(ScreenOrientation::from(1), ScreenOrientation::Down);
+        assert_eq!(ScreenOrientation::from(2), ScreenOrientation::Up);
+        assert_eq!(ScreenOrientation::from(3), ScreenOrientation::Right);
+        assert_eq!(ScreenOrientation::from(4), ScreenOrientation::Left);
+        assert_eq!(ScreenOrientation::from(7), ScreenOrientation::Unknown(7));
+        for value in 0..6 {
+            let orientation = ScreenOrientation::from(value);
+            assert_eq!(i32::from(orientation), value);
+        }
+        assert!(ScreenOrientation::Left.is_landscape());
+        assert!(ScreenOrientation::Up.is_portrait());
+        assert!(!ScreenOrientation::Unknown(0).is_portrait());
+        assert!(!ScreenOrientation::Unknown(0).is_landscape());
+    }
+
+    #[test]
+    fn test_find_region() {
+        let info = MatchInfo {
+            rect: Rect::new(100, 100, 40, 40),
+            score: None,
+        };
+        assert!(FindBuilder::new().build().accepts(&info));
+        let toolbar = FindBuilder::new()
+            .region(Rect::new(0, 0, 1170, 200))
+            .build();
+        assert!(toolbar.accepts(&info));
+        let dialog = FindBuilder::new()
+            .region(Rect::new(0, 800, 1170, 900))
+            .build();
+        assert!(!dialog.accepts(&info));
+    }
+}
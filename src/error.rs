@@ -4,6 +4,10 @@ use thiserror::Error;
 pub enum Error {
     #[error("socket error: {0}")]
     SocketError(#[from] std::io::Error),
+    #[error("invalid touch: {0}")]
+    InvalidTouch(String),
+    #[error("not found: {0}")]
+    NotFound(String),
     #[error("{0}")]
     Err(String),
 }
@@ -0,0 +1,296 @@
+use crate::entity::{TouchFinger, TouchType};
+use crate::error::Error;
+use crate::geometry::{Point, Size};
+use crate::zx_touch::ZxTouch;
+
+/// 协议中坐标乘以 10 后取整占 5 位,即精度为 0.1
+pub const MAX_COORDINATE: f64 = 9999.9;
+
+/// 检查坐标是否能被协议编码,并且位于屏幕范围内
+pub(crate) fn validate_coordinate(point: Point, screen_size: Option<Size>) -> Result<(), Error> {
+    let range = 0.0..=MAX_COORDINATE;
+    if !range.contains(&point.x) || !range.contains(&point.y) {
+        return Err(Error::InvalidTouch(format!(
+            "coordinate ({}, {}) outside 0~{}",
+            point.x, point.y, MAX_COORDINATE
+        )));
+    }
+    if let Some(size) = screen_size {
+        if point.x >= size.width || point.y >= size.height {
+            return Err(Error::InvalidTouch(format!(
+                "coordinate ({}, {}) outside screen {}x{}",
+                point.x, point.y, size.width, size.height
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 把设备像素坐标限制在屏幕以内,坐标精度为 0.1
+pub(crate) fn clamp_to_screen(point: Point, screen_size: Size) -> Point {
+    Point::new(
+        point.x.clamp(0.0, (screen_size.width - 0.1).max(0.0)),
+        point.y.clamp(0.0, (screen_size.height - 0.1).max(0.0)),
+    )
+}
+
+/// 记录每根手指是否被占用、是否按下以及按下的位置
+#[derive(Debug, Default)]
+pub(crate) struct FingerTable {
+    allocated: [bool; 5],
+    positions: [Option<Point>; 5],
+}
+
+impl FingerTable {
+    pub(crate) fn allocate(&mut self) -> Option<TouchFinger> {
+        let finger = TouchFinger::ALL.into_iter().find(|finger| {
+            let index = finger.index();
+            !self.allocated[index] && self.positions[index].is_none()
+        })?;
+        self.allocated[finger.index()] = true;
+        Some(finger)
+    }
+    pub(crate) fn free(&mut self, finger: TouchFinger) {
+        self.allocated[finger.index()] = false;
+    }
+    pub(crate) fn record(&mut self, touch_type: TouchType, point: Point, finger: TouchFinger) {
+        self.positions[finger.index()] = match touch_type {
+            TouchType::Down | TouchType::Move => Some(point),
+            TouchType::Up => None,
+        };
+    }
+    pub(crate) fn position(&self, finger: TouchFinger) -> Option<Point> {
+        self.positions[finger.index()]
+    }
+    /// 按 按下→移动→抬起 的顺序检查一批事件,不修改当前状态
+    pub(crate) fn validate(
+        &self,
+        events: &[(TouchType, Point, TouchFinger)],
+        screen_size: Option<Size>,
+    ) -> Result<(), Error> {
+        let mut down = self.positions.map(|position| position.is_some());
+        for &(touch_type, point, finger) in events {
+            validate_coordinate(point, screen_size)?;
+            let index = finger.index();
+            match (touch_type, down[index]) {
+                (TouchType::Down, true) => {
+                    return Err(Error::InvalidTouch(format!("{:?} is already down", finger)));
+                }
+                (TouchType::Move | TouchType::Up, false) => {
+                    return Err(Error::InvalidTouch(format!(
+                        "{:?} {:?} before Down",
+                        finger, touch_type
+                    )));
+                }
+                _ => {}
+            }
+            down[index] = !matches!(touch_type, TouchType::Up);
+        }
+        Ok(())
+    }
+    pub(crate) fn held(&self) -> Vec<(TouchFinger, Point)> {
+        TouchFinger::ALL
+            .into_iter()
+            .filter_map(|finger| self.position(finger).map(|point| (finger, point)))
+            .collect()
+    }
+}
+
+/// 占用中的手指,释放时如果仍处于按下状态会自动抬起
+pub struct FingerGuard<'a> {
+    touch: &'a ZxTouch,
+    finger: TouchFinger,
+}
+
+impl<'a> FingerGuard<'a> {
+    pub fn finger(&self) -> TouchFinger {
+        self.finger
+    }
+    /// 手指当前按下的位置(设备像素)
+    pub fn position(&self) -> Option<Point> {
+        self.touch.fingers.lock().unwrap().position(self.finger)
+    }
+    pub async fn down(&self, point: impl Into<Point>) -> Result<(), Error> {
+        self.touch.touch_down(point, self.finger).await
+    }
+    pub async fn move_to(&self, point: impl Into<Point>) -> Result<(), Error> {
+        self.touch.touch_move(point, self.finger).await
+    }
+    /// 在最后的位置抬起,未按下时什么也不做
+    pub async fn up(&self) -> Result<(), Error> {
+        match self.position() {
+            Some(point) => {
+                self.touch
+                    .basetouch(TouchType::Up, point, self.finger)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Drop for FingerGuard<'a> {
+    fn drop(&mut self) {
+        self.touch.release_blocking(self.finger);
+        self.touch.fingers.lock().unwrap().free(self.finger);
+    }
+}
+
+impl ZxTouch {
+    /// 分配一根空闲的手指
+    pub fn allocate_finger(&self) -> Result<FingerGuard<'_>, Error> {
+        let finger = self
+            .fingers
+            .lock()
+            .unwrap()
+            .allocate()
+            .ok_or_else(|| Error::Err("no free finger".to_string()))?;
+        Ok(FingerGuard {
+            touch: self,
+            finger,
+        })
+    }
+    /// 一次分配多根空闲手指,不够时全部归还
+    pub fn allocate_fingers(&self, count: usize) -> Result<Vec<FingerGuard<'_>>, Error> {
+        (0..count).map(|_| self.allocate_finger()).collect()
+    }
+    /// 当前按下的手指及其位置(设备像素)
+    pub fn held_fingers(&self) -> Vec<(TouchFinger, Point)> {
+        self.fingers.lock().unwrap().held()
+    }
+    /// 抬起所有按下的手指,发送失败时同样把这些手指记为已抬起
+    pub async fn release_all(&self) -> Result<(), Error> {
+        let held = self.held_fingers();
+        if held.is_empty() {
+            return Ok(());
+        }
+        let events: Vec<_> = held
+            .into_iter()
+            .map(|(finger, point)| (TouchType::Up, point, finger))
+            .collect();
+        let result = self.send_touch_events(events.clone()).await;
+        if result.is_err() {
+            let mut fingers = self.fingers.lock().unwrap();
+            for (touch_type, point, finger) in events {
+                fingers.record(touch_type, point, finger);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_skips_held_fingers() {
+        let mut table = FingerTable::default();
+        table.record(TouchType::Down, Point::new(10, 20), TouchFinger::One);
+        assert!(matches!(table.allocate(), Some(TouchFinger::Two)));
+        assert!(matches!(table.allocate(), Some(TouchFinger::Three)));
+        table.free(TouchFinger::Two);
+        assert!(matches!(table.allocate(), Some(TouchFinger::Two)));
+        assert!(matches!(table.allocate(), Some(TouchFinger::Four)));
+        assert!(matches!(table.allocate(), Some(TouchFinger::Five)));
+        assert!(table.allocate().is_none());
+    }
+
+    #[test]
+    fn test_record_positions() {
+        let mut table = FingerTable::default();
+        table.record(TouchType::Down, Point::new(10, 20), TouchFinger::Three);
+        table.record(TouchType::Move, Point::new(30, 40), TouchFinger::Three);
+        assert_eq!(table.held(), vec![(TouchFinger::Three, Point::new(30, 40))]);
+        table.record(TouchType::Up, Point::new(30, 40), TouchFinger::Three);
+        assert!(table.held().is_empty());
+    }
+
+    #[test]
+    fn test_validate_sequence() {
+        let mut table = FingerTable::default();
+        assert!(table
+            .validate(
+                &[
+                    (TouchType::Down, Point::new(1, 1), TouchFinger::One),
+                    (TouchType::Move, Point::new(2, 2), TouchFinger::One),
+                    (TouchType::Up, Point::new(2, 2), TouchFinger::One),
+                    (TouchType::Down, Point::new(3, 3), TouchFinger::One),
+                ],
+                None,
+            )
+            .is_ok());
+        assert!(matches!(
+            table.validate(
+                &[(TouchType::Move, Point::new(1, 1), TouchFinger::Two)],
+                None
+            ),
+            Err(Error::InvalidTouch(_))
+        ));
+        assert!(matches!(
+            table.validate(&[(TouchType::Up, Point::new(1, 1), TouchFinger::Two)], None),
+            Err(Error::InvalidTouch(_))
+        ));
+        table.record(TouchType::Down, Point::new(1, 1), TouchFinger::Two);
+        assert!(matches!(
+            table.validate(
+                &[(TouchType::Down, Point::new(1, 1), TouchFinger::Two)],
+                None
+            ),
+            Err(Error::InvalidTouch(_))
+        ));
+        assert!(table
+            .validate(&[(TouchType::Up, Point::new(1, 1), TouchFinger::Two)], None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_coordinate() {
+        assert!(validate_coordinate(Point::new(9999, 0), None).is_ok());
+        assert!(validate_coordinate(Point::new(10000, 0), None).is_err());
+        assert!(validate_coordinate(Point::new(-0.5, 0.0), None).is_err());
+        assert!(validate_coordinate(Point::new(f64::NAN, 0.0), None).is_err());
+        assert!(
+            validate_coordinate(Point::new(1169.5, 2531.5), Some(Size::new(1170, 2532))).is_ok()
+        );
+        assert!(validate_coordinate(Point::new(1169, 2531), Some(Size::new(1170, 2532))).is_ok());
+        assert!(validate_coordinate(Point::new(1170, 100), Some(Size::new(1170, 2532))).is_err());
+        assert!(validate_coordinate(Point::new(100, 2532), Some(Size::new(1170, 2532))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_release_without_connection() {
+        let touch = ZxTouch::new("127.0.0.1", 6000);
+        {
+            let guard = touch.allocate_finger().unwrap();
+            touch.fingers.lock().unwrap().record(
+                TouchType::Down,
+                Point::new(10, 20),
+                guard.finger(),
+            );
+        }
+        assert!(touch.held_fingers().is_empty());
+        touch
+            .fingers
+            .lock()
+            .unwrap()
+            .record(TouchType::Down, Point::new(10, 20), TouchFinger::Two);
+        assert!(touch.release_all().await.is_err());
+        assert!(touch.held_fingers().is_empty());
+        assert_eq!(touch.allocate_fingers(5).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_guard_without_connection() {
+        let touch = ZxTouch::new("127.0.0.1", 6000);
+        {
+            let guards = touch.allocate_fingers(5).unwrap();
+            assert_eq!(guards.len(), 5);
+            assert!(touch.allocate_finger().is_err());
+        }
+        assert!(matches!(
+            touch.allocate_finger().map(|guard| guard.finger()),
+            Ok(TouchFinger::One)
+        ));
+    }
+}
@@ -0,0 +1,228 @@
+/// 屏幕上的点
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// 宽高
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// 以左上角为原点的矩形
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+unsafe impl Send for Point {}
+unsafe impl Sync for Point {}
+unsafe impl Send for Size {}
+unsafe impl Sync for Size {}
+unsafe impl Send for Rect {}
+unsafe impl Sync for Rect {}
+
+impl Point {
+    pub fn new(x: impl Into<f64>, y: impl Into<f64>) -> Self {
+        Self {
+            x: x.into(),
+            y: y.into(),
+        }
+    }
+    pub fn offset(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Self {
+            x: self.x * sx,
+            y: self.y * sy,
+        }
+    }
+    pub fn distance(&self, other: Point) -> f64 {
+        (other.x - self.x).hypot(other.y - self.y)
+    }
+    /// 线性插值,`t` 为 0 时是自身,为 1 时是 `other`
+    pub fn lerp(&self, other: Point, t: f64) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+        }
+    }
+}
+
+impl Size {
+    pub fn new(width: impl Into<f64>, height: impl Into<f64>) -> Self {
+        Self {
+            width: width.into(),
+            height: height.into(),
+        }
+    }
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Self {
+            width: self.width * sx,
+            height: self.height * sy,
+        }
+    }
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+}
+
+impl Rect {
+    pub fn new(
+        x: impl Into<f64>,
+        y: impl Into<f64>,
+        width: impl Into<f64>,
+        height: impl Into<f64>,
+    ) -> Self {
+        Self {
+            x: x.into(),
+            y: y.into(),
+            width: width.into(),
+            height: height.into(),
+        }
+    }
+    pub fn from_origin_size(origin: Point, size: Size) -> Self {
+        Self {
+            x: origin.x,
+            y: origin.y,
+            width: size.width,
+            height: size.height,
+        }
+    }
+    /// 由任意两个对角点构造
+    pub fn from_corners(a: Point, b: Point) -> Self {
+        Self {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+            width: (b.x - a.x).abs(),
+            height: (b.y - a.y).abs(),
+        }
+    }
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+    pub fn center(&self) -> Point {
+        Point::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.x && point.x < self.right() && point.y >= self.y && point.y < self.bottom()
+    }
+    /// 完全包含另一个矩形
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+    /// 相交部分,不相交时返回 `None`
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return None;
+        }
+        Some(Rect::new(x, y, right - x, bottom - y))
+    }
+    /// 交并比,用于判断两个矩形是否重叠
+    pub fn iou(&self, other: &Rect) -> f64 {
+        let inter = self.intersect(other).map_or(0.0, |rect| rect.area());
+        let union = self.area() + other.area() - inter;
+        if union <= 0.0 {
+            return 0.0;
+        }
+        inter / union
+    }
+    pub fn offset(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Self {
+            x: self.x * sx,
+            y: self.y * sy,
+            width: self.width * sx,
+            height: self.height * sy,
+        }
+    }
+}
+
+impl<X: Into<f64>, Y: Into<f64>> From<(X, Y)> for Point {
+    fn from((x, y): (X, Y)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl<W: Into<f64>, H: Into<f64>> From<(W, H)> for Size {
+    fn from((width, height): (W, H)) -> Self {
+        Size::new(width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point() {
+        let point: Point = (10, 20).into();
+        assert_eq!(point, Point::new(10.0, 20.0));
+        assert_eq!(point.offset(-5.0, 5.0), Point::new(5, 25));
+        assert_eq!(point.scale(2.0, 0.5), Point::new(20, 10));
+        assert_eq!(Point::new(0, 0).distance(Point::new(3, 4)), 5.0);
+        assert_eq!(point.lerp(Point::new(20, 40), 0.5), Point::new(15, 30));
+    }
+
+    #[test]
+    fn test_rect() {
+        let rect = Rect::new(10, 20, 100, 50);
+        assert_eq!(rect.center(), Point::new(60, 45));
+        assert_eq!(rect.size(), Size::new(100, 50));
+        assert!(rect.contains(Point::new(10, 20)));
+        assert!(!rect.contains(Point::new(110, 20)));
+        assert!(rect.contains_rect(&Rect::new(20, 30, 10, 10)));
+        assert_eq!(rect.offset(5.0, -5.0), Rect::new(15, 15, 100, 50));
+        assert_eq!(rect.scale(2.0, 2.0), Rect::new(20, 40, 200, 100));
+        assert_eq!(
+            Rect::from_corners(Point::new(110, 70), Point::new(10, 20)),
+            rect
+        );
+    }
+
+    #[test]
+    fn test_intersect() {
+        let a = Rect::new(0, 0, 100, 100);
+        let b = Rect::new(50, 60, 100, 100);
+        assert_eq!(a.intersect(&b), Some(Rect::new(50, 60, 50, 40)));
+        assert_eq!(a.intersect(&Rect::new(100, 0, 10, 10)), None);
+        assert_eq!(a.iou(&a), 1.0);
+        assert_eq!(a.iou(&Rect::new(50, 0, 100, 100)), 1.0 / 3.0);
+        assert_eq!(a.iou(&Rect::new(100, 0, 10, 10)), 0.0);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::entity::{GestureBuilder, SwipeBuilder, TouchFinger, TouchType};
+use crate::error::Error;
+use crate::geometry::Point;
+use crate::zx_touch::ZxTouch;
+
+pub type TouchFrame = Vec<(TouchType, Point, TouchFinger)>;
+
+/// 缓动曲线,把时间进度 `t`(0~1)映射为位移进度
+#[derive(Debug, Clone, Copy)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// 与 css `cubic-bezier(x1, y1, x2, y2)` 相同
+    CubicBezier(f32, f32, f32, f32),
+}
+
+unsafe impl Send for Easing {}
+unsafe impl Sync for Easing {}
+
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let bezier = |p1: f64, p2: f64, s: f64| {
+                    3.0 * p1 * s * (1.0 - s).powi(2) + 3.0 * p2 * s * s * (1.0 - s) + s.powi(3)
+                };
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..32 {
+                    let mid = (low + high) / 2.0;
+                    if bezier(x1 as f64, x2 as f64, mid) < t {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                bezier(y1 as f64, y2 as f64, (low + high) / 2.0)
+            }
+        }
+    }
+}
+
+fn to_coord(point: Point) -> Point {
+    Point::new(point.x.max(0.0), point.y.max(0.0))
+}
+
+/// 计算第 `step` 步时两根手指的位置
+fn finger_positions(builder: &GestureBuilder, step: u32) -> [Point; 2] {
+    let t = if builder.steps == 0 {
+        1.0
+    } else {
+        builder.easing.apply(step as f64 / builder.steps as f64)
+    };
+    let lerp = |from: f64, to: f64| from + (to - from) * t;
+    let center = builder
+        .center
+        .offset(builder.offset.x * t, builder.offset.y * t);
+    let radius = lerp(builder.start_radius, builder.end_radius);
+    let angle = lerp(builder.start_angle as f64, builder.end_angle as f64).to_radians();
+    let (dx, dy) = (radius * angle.cos(), radius * angle.sin());
+    [
+        to_coord(center.offset(-dx, -dy)),
+        to_coord(center.offset(dx, dy)),
+    ]
+}
+
+/// fling 模式下的位移进度:起点速度为 0,终点速度为 `end_slope`(按全程平均速度归一化)
+fn fling_progress(t: f64, end_slope: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    (3.0 * t * t - 2.0 * t.powi(3)) + (t.powi(3) - t * t) * end_slope
+}
+
+/// 按采样率生成滑动轨迹上的所有点(包含起点和终点)
+pub fn swipe_path(builder: &SwipeBuilder) -> Vec<Point> {
+    let (from, to) = (builder.from, builder.to);
+    let samples = (builder.duration as u64 * builder.sample_rate as u64 / 1000).max(1);
+    let distance = from.distance(to);
+    // 终点速度换算成相对平均速度的斜率,超过 3 会让轨迹先后退再前进
+    let end_slope = builder.fling.filter(|_| distance > 0.0).map(|velocity| {
+        (velocity as f64 * builder.duration as f64 / 1000.0 / distance).clamp(0.0, 3.0)
+    });
+    (0..=samples)
+        .map(|sample| {
+            let t = sample as f64 / samples as f64;
+            let progress = match end_slope {
+                Some(end_slope) => fling_progress(t, end_slope),
+                None => builder.easing.apply(t),
+            };
+            to_coord(from.lerp(to, progress))
+        })
+        .collect()
+}
+
+/// 生成双指手势的所有触摸帧:按下、`steps` 次移动、抬起
+pub fn two_finger_frames(builder: &GestureBuilder, fingers: [TouchFinger; 2]) -> Vec<TouchFrame> {
+    let frame = |touch_type: TouchType, step: u32| -> TouchFrame {
+        finger_positions(builder, step)
+            .into_iter()
+            .zip(fingers)
+            .map(|(point, finger)| (touch_type, point, finger))
+            .collect()
+    };
+    let mut frames = vec![frame(TouchType::Down, 0)];
+    for step in 1..=builder.steps {
+        frames.push(frame(TouchType::Move, step));
+    }
+    frames.push(frame(TouchType::Up, builder.steps));
+    frames
+}
+
+impl ZxTouch {
+    /// 双指手势
+    pub async fn gesture(&self, builder: GestureBuilder) -> Result<(), Error> {
+        let interval = builder.duration / builder.steps.max(1);
+        let guards = self.allocate_fingers(2)?;
+        let frames = two_finger_frames(&builder, [guards[0].finger(), guards[1].finger()]);
+        let last = frames.len() - 1;
+        for (index, frame) in frames.into_iter().enumerate() {
+            if index > 0 && index < last {
+                self.delay(interval).await;
+            }
+            self.touch_events(frame).await?;
+        }
+        Ok(())
+    }
+    /// 双指捏合(缩小)
+    pub async fn pinch_in(
+        &self,
+        center: impl Into<Point>,
+        radius: impl Into<f64>,
+        duration: u32,
+    ) -> Result<(), Error> {
+        let radius = radius.into();
+        let builder = GestureBuilder::new()
+            .center(center)
+            .radius(radius, radius / 5.0)
+            .duration(duration)
+            .build();
+        self.gesture(builder).await
+    }
+    /// 双指张开(放大)
+    pub async fn pinch_out(
+        &self,
+        center: impl Into<Point>,
+        radius: impl Into<f64>,
+        duration: u32,
+    ) -> Result<(), Error> {
+        let radius = radius.into();
+        let builder = GestureBuilder::new()
+            .center(center)
+            .radius(radius / 5.0, radius)
+            .duration(duration)
+            .build();
+        self.gesture(builder).await
+    }
+    /// 双指旋转,`degrees` 为正时顺时针
+    pub async fn rotate(
+        &self,
+        center: impl Into<Point>,
+        radius: impl Into<f64>,
+        degrees: f32,
+        duration: u32,
+    ) -> Result<(), Error> {
+        let radius = radius.into();
+        let builder = GestureBuilder::new()
+            .center(center)
+            .radius(radius, radius)
+            .angle(0.0, degrees)
+            .duration(duration)
+            .build();
+        self.gesture(builder).await
+    }
+    /// 双指平移
+    pub async fn two_finger_pan(
+        &self,
+        from: impl Into<Point>,
+        to: impl Into<Point>,
+        duration: u32,
+    ) -> Result<(), Error> {
+        let (from, to) = (from.into(), to.into());
+        let builder = GestureBuilder::new()
+            .center(from)
+            .radius(50, 50)
+            .offset((to.x - from.x, to.y - from.y))
+            .duration(duration)
+            .build();
+        self.gesture(builder).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 取整后比较,避免三角函数的浮点误差
+    fn rounded(frame: &TouchFrame, index: usize) -> (TouchType, i64, i64, TouchFinger) {
+        let (touch_type, point, finger) = frame[index];
+        (
+            touch_type,
+            point.x.round() as i64,
+            point.y.round() as i64,
+            finger,
+        )
+    }
+
+    #[test]
+    fn test_pinch_frames() {
+        let builder = GestureBuilder::new()
+            .center((500, 500))
+            .radius(200, 40)
+            .steps(4)
+            .build();
+        let frames = two_finger_frames(&builder, [TouchFinger::One, TouchFinger::Two]);
+        assert_eq!(frames.len(), 6);
+        assert_eq!(
+            rounded(&frames[0], 0),
+            (TouchType::Down, 300, 500, TouchFinger::One)
+        );
+        assert_eq!(
+            rounded(&frames[0], 1),
+            (TouchType::Down, 700, 500, TouchFinger::Two)
+        );
+        assert!(matches!(
+            rounded(&frames[2], 0),
+            (TouchType::Move, 380, 500, _)
+        ));
+        assert_eq!(
+            rounded(&frames[5], 1),
+            (TouchType::Up, 540, 500, TouchFinger::Two)
+        );
+    }
+
+    #[test]
+    fn test_rotate_and_pan_frames() {
+        let builder = GestureBuilder::new()
+            .center((500, 500))
+            .radius(100, 100)
+            .angle(0.0, 90.0)
+            .offset((-100, 0))
+            .steps(2)
+            .build();
+        let frames = two_finger_frames(&builder, [TouchFinger::One, TouchFinger::Two]);
+        assert!(matches!(
+            rounded(&frames[3], 0),
+            (TouchType::Up, 400, 400, _)
+        ));
+        assert!(matches!(
+            rounded(&frames[3], 1),
+            (TouchType::Up, 400, 600, _)
+        ));
+    }
+
+    #[test]
+    fn test_easing_endpoints() {
+        let curves = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+        ];
+        for easing in curves {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!((Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3) - 0.3).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_swipe_path_left_and_up() {
+        let builder = SwipeBuilder::new()
+            .from((600, 900))
+            .to((100, 200))
+            .duration(100)
+            .sample_rate(50)
+            .build();
+        let path = swipe_path(&builder);
+        assert_eq!(path.len(), 6);
+        assert_eq!(path[0], Point::new(600, 900));
+        assert_eq!(path[5], Point::new(100, 200));
+        assert!(path
+            .windows(2)
+            .all(|w| w[1].x <= w[0].x && w[1].y <= w[0].y));
+    }
+
+    #[test]
+    fn test_fling_path_accelerates() {
+        let builder = SwipeBuilder::new()
+            .from((0, 0))
+            .to((0, 1000))
+            .duration(100)
+            .sample_rate(100)
+            .fling(20000.0)
+            .build();
+        let path = swipe_path(&builder);
+        assert_eq!(path.last(), Some(&Point::new(0, 1000)));
+        let first_step = path[1].y - path[0].y;
+        let last_step = path[10].y - path[9].y;
+        assert!(last_step > first_step * 5.0);
+    }
+
+    #[test]
+    fn test_frames_clamp_to_screen_origin() {
+        let builder = GestureBuilder::new()
+            .center((10, 10))
+            .radius(50, 50)
+            .build();
+        let frames = two_finger_frames(&builder, [TouchFinger::One, TouchFinger::Two]);
+        assert!(matches!(
+            rounded(&frames[0], 0),
+            (TouchType::Down, 0, 10, _)
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::error::Error;
+use crate::finger::clamp_to_screen;
+use crate::geometry::{Point, Rect, Size};
+use crate::random::Rng;
+use crate::zx_touch::ZxTouch;
+
+/// 拟人化参数,开启后点击和滑动的位置、时长都带有随机性
+#[derive(Debug, Clone)]
+pub struct HumanizeProfile {
+    /// 点击位置的标准差,为目标区域半宽、半高的比例
+    pub position_sigma: f64,
+    /// 直接点击坐标时,把坐标周围该半径(设备像素)的正方形当作目标区域
+    pub tap_radius: f64,
+    /// 按下时长的均值和标准差(毫秒)
+    pub press_mean: u32,
+    pub press_sigma: u32,
+    pub press_min: u32,
+    /// 滑动轨迹最大弯曲幅度,为滑动距离的比例
+    pub curvature: f64,
+    /// 滑动总时长和每一步间隔的随机浮动比例
+    pub speed_variation: f64,
+    /// 随机数种子,相同种子可以复现同样的操作,默认使用当前时间
+    pub seed: Option<u64>,
+}
+
+unsafe impl Send for HumanizeProfile {}
+unsafe impl Sync for HumanizeProfile {}
+
+impl HumanizeProfile {
+    pub fn new() -> Self {
+        Self {
+            position_sigma: 0.3,
+            tap_radius: 4.0,
+            press_mean: 90,
+            press_sigma: 25,
+            press_min: 40,
+            curvature: 0.1,
+            speed_variation: 0.2,
+            seed: None,
+        }
+    }
+    pub fn position_sigma(&mut self, position_sigma: f64) -> &mut Self {
+        self.position_sigma = position_sigma;
+        self
+    }
+    pub fn tap_radius(&mut self, tap_radius: f64) -> &mut Self {
+        self.tap_radius = tap_radius;
+        self
+    }
+    pub fn press(&mut self, press_mean: u32, press_sigma: u32) -> &mut Self {
+        self.press_mean = press_mean;
+        self.press_sigma = press_sigma;
+        self
+    }
+    pub fn press_min(&mut self, press_min: u32) -> &mut Self {
+        self.press_min = press_min;
+        self
+    }
+    pub fn curvature(&mut self, curvature: f64) -> &mut Self {
+        self.curvature = curvature;
+        self
+    }
+    pub fn speed_variation(&mut self, speed_variation: f64) -> &mut Self {
+        self.speed_variation = speed_variation;
+        self
+    }
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for HumanizeProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 拟人化参数及其随机数状态
+#[derive(Debug, Clone)]
+pub(crate) struct Humanizer {
+    profile: HumanizeProfile,
+    rng: Rng,
+}
+
+impl Humanizer {
+    const MAX_RESAMPLE: usize = 8;
+
+    pub(crate) fn new(profile: HumanizeProfile) -> Self {
+        let rng = profile.seed.map(Rng::new).unwrap_or_else(Rng::from_time);
+        Self { profile, rng }
+    }
+    pub(crate) fn profile(&self) -> &HumanizeProfile {
+        &self.profile
+    }
+    /// 以中心为均值的正态分布取点,落在区域外时重新采样,多次失败后截断到区域内(坐标精度为 0.1)
+    pub(crate) fn point_in(&mut self, rect: Rect) -> Point {
+        let center = rect.center();
+        let sigma = self.profile.position_sigma.max(0.0);
+        let (sx, sy) = (rect.width / 2.0 * sigma, rect.height / 2.0 * sigma);
+        let mut point = center;
+        for _ in 0..Self::MAX_RESAMPLE {
+            point = center.offset(self.rng.gaussian() * sx, self.rng.gaussian() * sy);
+            if rect.contains(point) {
+                return point;
+            }
+        }
+        Point::new(
+            point.x.clamp(rect.x, (rect.right() - 0.1).max(rect.x)),
+            point.y.clamp(rect.y, (rect.bottom() - 0.1).max(rect.y)),
+        )
+    }
+    /// 设备像素坐标附近的点,限制在 `screen` 以内
+    pub(crate) fn point_near(&mut self, point: Point, screen: Size) -> Point {
+        let radius = self.profile.tap_radius.max(0.0);
+        let rect = Rect::new(
+            point.x - radius,
+            point.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        );
+        let screen = Rect::from_origin_size(Point::default(), screen);
+        self.point_in(rect.intersect(&screen).unwrap_or(rect))
+    }
+    pub(crate) fn press_duration(&mut self) -> u32 {
+        let duration =
+            self.profile.press_mean as f64 + self.rng.gaussian() * self.profile.press_sigma as f64;
+        duration.round().max(self.profile.press_min as f64) as u32
+    }
+    fn vary(&mut self, value: f64) -> f64 {
+        let variation = self.profile.speed_variation.clamp(0.0, 0.9);
+        value * (1.0 + variation * self.rng.range(-1.0, 1.0))
+    }
+    /// 滑动总时长
+    pub(crate) fn duration(&mut self, duration: u32) -> u32 {
+        self.vary(duration as f64).round() as u32
+    }
+    /// 把 `duration` 分成 `steps` 段不等长的间隔
+    pub(crate) fn intervals(&mut self, duration: u32, steps: usize) -> Vec<u32> {
+        let weights: Vec<f64> = (0..steps).map(|_| self.vary(1.0)).collect();
+        let total: f64 = weights.iter().sum();
+        weights
+            .iter()
+            .map(|weight| (duration as f64 * weight / total).round() as u32)
+            .collect()
+    }
+    /// 设备像素轨迹沿垂直方向弯成弧线,起点和终点不变,弯曲后的点限制在 `screen` 以内
+    pub(crate) fn curve(&mut self, path: &[Point], screen: Size) -> Vec<Point> {
+        let (Some(&from), Some(&to)) = (path.first(), path.last()) else {
+            return path.to_vec();
+        };
+        let distance = from.distance(to);
+        if distance == 0.0 || path.len() < 3 {
+            return path.to_vec();
+        }
+        let bow = self.rng.range(-1.0, 1.0) * self.profile.curvature * distance;
+        let (nx, ny) = (-(to.y - from.y) / distance, (to.x - from.x) / distance);
+        let last = (path.len() - 1) as f64;
+        path.iter()
+            .enumerate()
+            .map(|(index, point)| {
+                let offset = bow * (std::f64::consts::PI * index as f64 / last).sin();
+                clamp_to_screen(point.offset(nx * offset, ny * offset), screen)
+            })
+            .collect()
+    }
+}
+
+impl ZxTouch {
+    /// 设置拟人化参数,之后 `touch`、`touch_rect`、`touch_image` 和 `swipe` 都会带上随机性,`None` 关闭
+    pub fn set_humanize_profile(&mut self, profile: Option<HumanizeProfile>) {
+        *self.humanizer.lock().unwrap() = profile.map(Humanizer::new);
+    }
+    pub fn humanize_profile(&self) -> Option<HumanizeProfile> {
+        self.humanizer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|humanizer| humanizer.profile().clone())
+    }
+    /// 未开启拟人化时返回 `None`
+    pub(crate) fn humanize<T>(&self, f: impl FnOnce(&mut Humanizer) -> T) -> Option<T> {
+        self.humanizer.lock().unwrap().as_mut().map(f)
+    }
+    /// 坐标附近的点。`tap_radius` 是设备像素,先换算到设备像素再取点,然后换算回当前坐标系
+    pub(crate) async fn humanize_near(&self, point: Point) -> Result<Point, Error> {
+        if self.humanizer.lock().unwrap().is_none() {
+            return Ok(point);
+        }
+        let device = self.to_device(point).await?;
+        let screen = self.device_screen_size().await?;
+        match self.humanize(|humanizer| humanizer.point_near(device, screen)) {
+            Some(near) => self.from_device(near).await,
+            None => Ok(point),
+        }
+    }
+    /// 滑动轨迹弯成弧线。弯曲在设备像素下计算并限制在屏幕以内,再换算回当前坐标系
+    pub(crate) async fn humanize_curve(&self, path: &[Point]) -> Result<Vec<Point>, Error> {
+        if self.humanizer.lock().unwrap().is_none() {
+            return Ok(path.to_vec());
+        }
+        let mut device = Vec::with_capacity(path.len());
+        for &point in path {
+            device.push(self.to_device(point).await?);
+        }
+        let screen = self.device_screen_size().await?;
+        let Some(curved) = self.humanize(|humanizer| humanizer.curve(&device, screen)) else {
+            return Ok(path.to_vec());
+        };
+        let mut result = Vec::with_capacity(curved.len());
+        for point in curved {
+            result.push(self.from_device(point).await?);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::{CoordinateSpace, ScreenMetrics};
+    use crate::finger::validate_coordinate;
+
+    fn humanizer() -> Humanizer {
+        Humanizer::new(HumanizeProfile::new().seed(3).build())
+    }
+
+    #[test]
+    fn test_point_in() {
+        let rect = Rect::new(100, 200, 80, 40);
+        let mut a = humanizer();
+        let mut b = humanizer();
+        let points: Vec<Point> = (0..1000).map(|_| a.point_in(rect)).collect();
+        assert!(points.iter().all(|point| rect.contains(*point)));
+        assert_eq!(
+            points[..10],
+            (0..10).map(|_| b.point_in(rect)).collect::<Vec<_>>()
+        );
+        assert!(points.iter().any(|point| *point != rect.center()));
+        let wide = Humanizer::new(HumanizeProfile::new().position_sigma(5.0).seed(1).build())
+            .point_in(rect);
+        assert!(rect.contains(wide));
+    }
+
+    #[test]
+    fn test_point_near_normalized() {
+        let screen = ScreenMetrics {
+            size: Size::new(1170, 2532),
+            scale: 3.0,
+        };
+        let space = CoordinateSpace::Normalized;
+        let mut humanizer = humanizer();
+        for corner in [Point::new(0, 0), Point::new(1.0, 1.0), Point::new(0.5, 0.5)] {
+            let device = space.to_device(corner, &screen);
+            for _ in 0..200 {
+                let near = humanizer.point_near(device, screen.size);
+                assert!(validate_coordinate(near, Some(screen.size)).is_ok());
+                assert!(near.distance(device) <= 4.0 * 2f64.sqrt());
+                let back = space.from_device(near, &screen);
+                assert!((0.0..=1.0).contains(&back.x) && (0.0..=1.0).contains(&back.y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_press_duration() {
+        let mut humanizer = humanizer();
+        let durations: Vec<u32> = (0..1000).map(|_| humanizer.press_duration()).collect();
+        assert!(durations.iter().all(|&duration| duration >= 40));
+        let mean = durations.iter().sum::<u32>() as f64 / durations.len() as f64;
+        assert!((mean - 90.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_intervals() {
+        let mut humanizer = humanizer();
+        let intervals = humanizer.intervals(300, 30);
+        assert_eq!(intervals.len(), 30);
+        assert!(intervals
+            .iter()
+            .all(|&interval| (8..=12).contains(&interval)));
+        let total: u32 = intervals.iter().sum();
+        assert!(total.abs_diff(300) <= 15);
+        let duration = humanizer.duration(1000);
+        assert!((800..=1200).contains(&duration));
+    }
+
+    #[test]
+    fn test_curve_at_edge() {
+        // 沿屏幕边缘滑动时向外弯的部分被限制在屏幕以内
+        let screen = Size::new(1170, 2532);
+        let edges = [
+            (Point::new(0, 100), Point::new(0, 2400)),
+            (Point::new(1169.9, 2400), Point::new(1169.9, 100)),
+            (Point::new(100, 0), Point::new(1100, 0)),
+            (Point::new(1100, 2531.9), Point::new(100, 2531.9)),
+        ];
+        for seed in 0..20 {
+            let mut humanizer =
+                Humanizer::new(HumanizeProfile::new().curvature(0.5).seed(seed).build());
+            for (from, to) in edges {
+                let path: Vec<Point> = (0..=20).map(|i| from.lerp(to, i as f64 / 20.0)).collect();
+                let curved = humanizer.curve(&path, screen);
+                assert!(curved
+                    .iter()
+                    .all(|point| validate_coordinate(*point, Some(screen)).is_ok()));
+                assert_eq!(curved[0], from);
+            }
+        }
+    }
+
+    #[test]
+    fn test_curve() {
+        let mut humanizer = humanizer();
+        let path: Vec<Point> = (0..=10).map(|x| Point::new(x * 100, 500)).collect();
+        let curved = humanizer.curve(&path, Size::new(1170, 2532));
+        assert_eq!(curved.len(), path.len());
+        assert_eq!(curved[0], path[0]);
+        assert!((curved[10].y - 500.0).abs() < 1e-6);
+        assert!(curved.iter().all(|point| (point.y - 500.0).abs() <= 100.0));
+        assert!(curved.iter().zip(&path).all(|(a, b)| a.x == b.x));
+        assert_eq!(
+            humanizer.curve(&path[..2], Size::new(1170, 2532)),
+            path[..2].to_vec()
+        );
+    }
+}
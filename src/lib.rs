@@ -1,6 +1,30 @@
 #![doc = include_str!("../README.md")]
+#[cfg(feature = "image")]
+pub mod artifacts;
+pub mod clicker;
+pub mod compare;
+pub mod coordinate;
+#[cfg(feature = "experimental")]
+pub mod crazy_tap;
 pub mod entity;
 pub mod error;
+pub mod finger;
+pub mod geometry;
+pub mod gesture;
+pub mod humanize;
 pub(crate) mod macros;
+#[cfg(feature = "image")]
+pub mod matcher;
+pub mod matching;
+#[cfg(feature = "experimental")]
+pub mod ocr;
+pub mod operation;
+pub mod random;
+pub mod screenshot;
+pub mod scroll;
+pub mod template;
+pub mod timeline;
+pub mod transfer;
 pub mod r#type;
+pub mod wait;
 pub mod zx_touch;
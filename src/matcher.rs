@@ -0,0 +1,556 @@
+use std::collections::HashMap;
+
+use crate::entity::{FindBuilder, MatchInfo};
+use crate::error::Error;
+use crate::geometry::Rect;
+use crate::matching::{non_max_suppression, MAX_OVERLAP};
+use crate::screenshot::Screenshot;
+use crate::template::TemplateImage;
+use crate::zx_touch::ZxTouch;
+
+/// 模板缩小到该尺寸以下时不再尝试
+const MIN_TEMPLATE_SIZE: u32 = 3;
+
+/// 灰度图及其积分图,用于快速计算任意窗口的均值和方差
+struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<f64>,
+    /// `(width + 1) × (height + 1)` 的积分图
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl GrayImage {
+    fn new(width: usize, height: usize, pixels: Vec<f64>) -> Self {
+        let stride = width + 1;
+        let mut sum = vec![0.0; stride * (height + 1)];
+        let mut sum_sq = vec![0.0; stride * (height + 1)];
+        for y in 0..height {
+            let (mut row, mut row_sq) = (0.0, 0.0);
+            for x in 0..width {
+                let value = pixels[y * width + x];
+                row += value;
+                row_sq += value * value;
+                sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row;
+                sum_sq[(y + 1) * stride + x + 1] = sum_sq[y * stride + x + 1] + row_sq;
+            }
+        }
+        Self {
+            width,
+            height,
+            pixels,
+            sum,
+            sum_sq,
+        }
+    }
+    fn from_screenshot(screenshot: &Screenshot) -> Self {
+        let pixels = screenshot
+            .pixels
+            .iter()
+            .map(|c| luma(c.r, c.g, c.b))
+            .collect();
+        Self::new(
+            screenshot.width as usize,
+            screenshot.height as usize,
+            pixels,
+        )
+    }
+    fn from_image(image: &image::RgbImage) -> Self {
+        let pixels = image.pixels().map(|p| luma(p[0], p[1], p[2])).collect();
+        Self::new(image.width() as usize, image.height() as usize, pixels)
+    }
+    /// 窗口内的和与平方和
+    fn window(&self, x: usize, y: usize, width: usize, height: usize) -> (f64, f64) {
+        let stride = self.width + 1;
+        let area = |table: &[f64]| {
+            table[(y + height) * stride + x + width]
+                - table[y * stride + x + width]
+                - table[(y + height) * stride + x]
+                + table[y * stride + x]
+        };
+        (area(&self.sum), area(&self.sum_sq))
+    }
+    /// 按 `factor × factor` 的块取平均缩小,不足一块的边缘丢弃
+    fn downsample(&self, factor: usize) -> Self {
+        let (width, height) = (self.width / factor, self.height / factor);
+        let area = (factor * factor) as f64;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(self.window(x * factor, y * factor, factor, factor).0 / area);
+            }
+        }
+        Self::new(width, height, pixels)
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> f64 {
+    0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+}
+
+/// 减去均值的模板,用于计算零均值归一化互相关
+struct Template {
+    width: usize,
+    height: usize,
+    centered: Vec<f64>,
+    variance: f64,
+}
+
+impl Template {
+    fn new(gray: &GrayImage) -> Self {
+        let count = (gray.width * gray.height) as f64;
+        let mean = gray.pixels.iter().sum::<f64>() / count;
+        let centered: Vec<f64> = gray.pixels.iter().map(|value| value - mean).collect();
+        let variance = centered.iter().map(|value| value * value).sum();
+        Self {
+            width: gray.width,
+            height: gray.height,
+            centered,
+            variance,
+        }
+    }
+    /// 纯色模板和任何位置都不相关
+    fn is_flat(&self) -> bool {
+        self.variance <= f64::EPSILON
+    }
+    /// 模板左上角放在 `(x, y)` 时的零均值归一化互相关
+    fn score(&self, image: &GrayImage, x: usize, y: usize) -> f64 {
+        let (tw, th) = (self.width, self.height);
+        let (sum, sum_sq) = image.window(x, y, tw, th);
+        let image_var = sum_sq - sum * sum / (tw * th) as f64;
+        if image_var <= f64::EPSILON || self.is_flat() {
+            return 0.0;
+        }
+        let mut cross = 0.0;
+        for ty in 0..th {
+            let row = &image.pixels[(y + ty) * image.width + x..][..tw];
+            let template_row = &self.centered[ty * tw..][..tw];
+            cross += row
+                .iter()
+                .zip(template_row)
+                .map(|(a, b)| a * b)
+                .sum::<f64>();
+        }
+        cross / (image_var * self.variance).sqrt()
+    }
+}
+
+/// 零均值归一化互相关,结果为 `(height - th + 1) × (width - tw + 1)` 的得分表
+fn ncc_scores(image: &GrayImage, template: &Template) -> Vec<f64> {
+    let (columns, rows) = (
+        image.width - template.width + 1,
+        image.height - template.height + 1,
+    );
+    let mut scores = vec![0.0; columns * rows];
+    if template.is_flat() {
+        return scores;
+    }
+    for y in 0..rows {
+        for x in 0..columns {
+            scores[y * columns + x] = template.score(image, x, y);
+        }
+    }
+    scores
+}
+
+/// 得分不低于阈值且为 3×3 邻域内最大值的位置
+fn local_maxima(
+    scores: &[f64],
+    columns: usize,
+    rows: usize,
+    threshold: f64,
+) -> Vec<(usize, usize)> {
+    let mut peaks = Vec::new();
+    for y in 0..rows {
+        for x in 0..columns {
+            let score = scores[y * columns + x];
+            if score < threshold {
+                continue;
+            }
+            let is_peak = (y.saturating_sub(1)..=(y + 1).min(rows - 1)).all(|ny| {
+                (x.saturating_sub(1)..=(x + 1).min(columns - 1))
+                    .all(|nx| scores[ny * columns + nx] <= score)
+            });
+            if is_peak {
+                peaks.push((x, y));
+            }
+        }
+    }
+    peaks
+}
+
+/// 粗搜索时模板缩小后的最小边长
+const COARSE_TEMPLATE_SIZE: usize = 8;
+/// 最大缩小倍数
+const MAX_COARSE_FACTOR: usize = 16;
+/// 缩小后得分会下降,粗搜索得分比阈值低这么多的峰值仍然细化
+const COARSE_MARGIN: f64 = 0.25;
+/// 每个缩放比例最多细化的峰值个数
+const MAX_CANDIDATES: usize = 32;
+/// 每层细化时在上一层位置周围搜索的半径
+const REFINE_RADIUS: usize = 2;
+
+/// 按块缩小的截图金字塔,同一次查找的各个缩放比例共用
+struct Pyramid {
+    levels: HashMap<usize, GrayImage>,
+}
+
+impl Pyramid {
+    fn new(image: GrayImage) -> Self {
+        Self {
+            levels: HashMap::from([(1, image)]),
+        }
+    }
+    fn level(&mut self, factor: usize) -> &GrayImage {
+        if !self.levels.contains_key(&factor) {
+            let level = self.levels[&1].downsample(factor);
+            self.levels.insert(factor, level);
+        }
+        &self.levels[&factor]
+    }
+    /// 模板至少保留 `COARSE_TEMPLATE_SIZE` 像素时最大的 2 的幂缩小倍数
+    fn coarse_factor(template: &GrayImage) -> usize {
+        let mut factor = 1;
+        while factor < MAX_COARSE_FACTOR
+            && template.width.min(template.height) / (factor * 2) >= COARSE_TEMPLATE_SIZE
+        {
+            factor *= 2;
+        }
+        factor
+    }
+    /// 查找模板的候选位置,返回原图上的 `(x, y, 得分)`,包括低于阈值的最佳位置。
+    /// 先在最小的一层算完整得分表,再逐层放大,只在峰值附近重新计算
+    fn search(&mut self, template: &GrayImage, threshold: f64) -> Vec<(usize, usize, f64)> {
+        let coarse = Self::coarse_factor(template);
+        let image = self.level(coarse);
+        let small = Template::new(&template.downsample(coarse));
+        if small.is_flat() || small.width > image.width || small.height > image.height {
+            return Vec::new();
+        }
+        let scores = ncc_scores(image, &small);
+        let (columns, rows) = (
+            image.width - small.width + 1,
+            image.height - small.height + 1,
+        );
+        let margin = if coarse > 1 { COARSE_MARGIN } else { 0.0 };
+        let mut peaks: Vec<(usize, usize, f64)> =
+            local_maxima(&scores, columns, rows, threshold - margin)
+                .into_iter()
+                .map(|(x, y)| (x, y, scores[y * columns + x]))
+                .collect();
+        // 最佳位置即使低于阈值也保留,作为候选框
+        if let Some(best) = (0..scores.len()).max_by(|&a, &b| scores[a].total_cmp(&scores[b])) {
+            let best = (best % columns, best / columns, scores[best]);
+            if !peaks.contains(&best) {
+                peaks.push(best);
+            }
+        }
+        peaks.sort_by(|a, b| b.2.total_cmp(&a.2));
+        peaks.truncate(MAX_CANDIDATES);
+        let mut factor = coarse;
+        while factor > 1 {
+            factor /= 2;
+            let image = self.level(factor);
+            let level = Template::new(&template.downsample(factor));
+            let (columns, rows) = (
+                image.width - level.width + 1,
+                image.height - level.height + 1,
+            );
+            for peak in peaks.iter_mut() {
+                let (x, y) = ((peak.0 * 2).min(columns - 1), (peak.1 * 2).min(rows - 1));
+                let mut best = (x, y, f64::MIN);
+                for ny in y.saturating_sub(REFINE_RADIUS)..=(y + REFINE_RADIUS).min(rows - 1) {
+                    for nx in x.saturating_sub(REFINE_RADIUS)..=(x + REFINE_RADIUS).min(columns - 1)
+                    {
+                        let score = level.score(image, nx, ny);
+                        if score > best.2 {
+                            best = (nx, ny, score);
+                        }
+                    }
+                }
+                *peak = best;
+            }
+        }
+        peaks
+    }
+}
+
+/// 在截图上查找模板,结果为设备竖屏像素,带相似度并按相似度从高到低排列,包括低于阈值的最佳候选。
+/// 与设备端查找一致,共尝试 `max_try_times` 个缩放比例,每次按 `scale_ration` 缩小模板
+pub(crate) fn match_candidates(
+    screenshot: &Screenshot,
+    template: &image::RgbImage,
+    find_builder: &FindBuilder,
+) -> Vec<MatchInfo> {
+    let mut pyramid = Pyramid::new(GrayImage::from_screenshot(screenshot));
+    let (width, height) = (screenshot.width as usize, screenshot.height as usize);
+    let step = screenshot.step.max(1) as f64;
+    let threshold = find_builder.acceptable as f64;
+    let mut matches = Vec::new();
+    let mut scale = 1.0;
+    for _ in 0..find_builder.max_try_times.max(1) {
+        let tw = (template.width() as f64 * scale / step).round() as u32;
+        let th = (template.height() as f64 * scale / step).round() as u32;
+        scale *= find_builder.scale_ration as f64;
+        if tw < MIN_TEMPLATE_SIZE || th < MIN_TEMPLATE_SIZE {
+            break;
+        }
+        if tw as usize > width || th as usize > height {
+            continue;
+        }
+        let resized =
+            image::imageops::resize(template, tw, th, image::imageops::FilterType::Triangle);
+        for (x, y, score) in pyramid.search(&GrayImage::from_image(&resized), threshold) {
+            matches.push(MatchInfo {
+                rect: Rect::new(
+                    screenshot.rect.x + x as f64 * step,
+                    screenshot.rect.y + y as f64 * step,
+                    tw as f64 * step,
+                    th as f64 * step,
+                ),
+                score: Some(score as f32),
+            });
+        }
+    }
+    non_max_suppression(matches, MAX_OVERLAP)
+}
+
+/// 在截图上查找模板的所有位置,只保留相似度不低于 `acceptable` 的结果
+pub fn match_template(
+    screenshot: &Screenshot,
+    template: &image::RgbImage,
+    find_builder: &FindBuilder,
+) -> Vec<MatchInfo> {
+    let threshold = find_builder.acceptable;
+    match_candidates(screenshot, template, find_builder)
+        .into_iter()
+        .filter(|info| info.score.is_some_and(|score| score >= threshold))
+        .collect()
+}
+
+/// 解码模板图片
+pub(crate) fn decode_template(data: &[u8]) -> Result<image::RgbImage, Error> {
+    Ok(image::load_from_memory(data)
+        .map_err(|e| Error::Err(e.to_string()))?
+        .to_rgb8())
+}
+
+impl ZxTouch {
+    /// 截图后在主机上查找模板的所有位置,结果带相似度并按相似度从高到低排列。
+    /// `step` 为截图的采样间隔
+    pub async fn host_find_all(
+        &self,
+        template: &TemplateImage,
+        find_builder: FindBuilder,
+        step: u32,
+    ) -> Result<Vec<MatchInfo>, Error> {
+        let (data, _) = template.load()?;
+        let template = decode_template(&data)?;
+        self.host_find(&template, &find_builder, step).await
+    }
+    /// 在 `find_builder.region`(未设置时为全屏)的截图上查找模板,结果为当前坐标系
+    pub(crate) async fn host_find(
+        &self,
+        template: &image::RgbImage,
+        find_builder: &FindBuilder,
+        step: u32,
+    ) -> Result<Vec<MatchInfo>, Error> {
+        let screenshot = match find_builder.region {
+            Some(region) => self.screenshot_region(region, step).await?,
+            None => self.screenshot(step).await?,
+        };
+        self.match_screenshot(&screenshot, template, find_builder)
+            .await
+    }
+    /// 在已有的截图上查找模板,结果换算成当前坐标系,只保留中心点在查找区域内的结果
+    pub(crate) async fn match_screenshot(
+        &self,
+        screenshot: &Screenshot,
+        template: &image::RgbImage,
+        find_builder: &FindBuilder,
+    ) -> Result<Vec<MatchInfo>, Error> {
+        let mut matches = Vec::new();
+        for info in match_template(screenshot, template, find_builder) {
+            let info = MatchInfo {
+                rect: self.from_device_rect(info.rect).await?,
+                score: info.score,
+            };
+            if find_builder.accepts(&info) {
+                matches.push(info);
+            }
+        }
+        Ok(matches)
+    }
+    /// 查找所有相似度不低于 `acceptable` 的结果,带相似度并按相似度从高到低排列。
+    /// 设备端 `TemplateMatch` 只返回一个不带相似度的结果,所以截图后在主机上查找。
+    /// `image_path` 可以是设备上的路径或模板库中的模板名,解码后的模板按名称缓存
+    pub async fn find_all(
+        &self,
+        image_path: &str,
+        find_builder: FindBuilder,
+    ) -> Result<Vec<MatchInfo>, Error> {
+        let template = self.template_image(image_path).await?;
+        self.host_find(&template, &find_builder, 1).await
+    }
+    /// 在查找区域内找最佳结果。设备端只能全屏查找,全屏最佳结果在区域外时会漏掉区域内的目标,
+    /// 所以只截取区域,在主机上查找。同时返回相似度最高的候选(设备竖屏像素),没找到时它低于阈值
+    pub(crate) async fn locate_in_region(
+        &self,
+        image_path: &str,
+        find_builder: &FindBuilder,
+    ) -> Result<(Option<MatchInfo>, Option<MatchInfo>), Error> {
+        let template = self.template_image(image_path).await?;
+        let region = find_builder.region.unwrap_or_default();
+        let screenshot = self.screenshot_region(region, 1).await?;
+        let candidates = match_candidates(&screenshot, &template, find_builder);
+        for candidate in &candidates {
+            if candidate.score < Some(find_builder.acceptable) {
+                break;
+            }
+            let info = MatchInfo {
+                rect: self.from_device_rect(candidate.rect).await?,
+                score: candidate.score,
+            };
+            if find_builder.accepts(&info) {
+                return Ok((Some(info), Some(candidate.clone())));
+            }
+        }
+        Ok((None, candidates.into_iter().next()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Color;
+
+    /// 灰色背景上的十字图案
+    fn pattern(size: u32) -> image::RgbImage {
+        image::RgbImage::from_fn(size, size, |x, y| {
+            let center = size / 2;
+            if x.abs_diff(center) <= size / 8 || y.abs_diff(center) <= size / 8 {
+                image::Rgb([250, 30, 30])
+            } else if x < center {
+                image::Rgb([40, 40, 200])
+            } else {
+                image::Rgb([90, 90, 90])
+            }
+        })
+    }
+
+    fn screen_with(patterns: &[(u32, u32, &image::RgbImage)]) -> Screenshot {
+        let mut screen = image::RgbImage::from_pixel(120, 100, image::Rgb([128, 128, 128]));
+        for &(x, y, pattern) in patterns {
+            image::imageops::overlay(&mut screen, pattern, x as i64, y as i64);
+        }
+        Screenshot::from_image(&screen, Rect::new(0, 0, 120, 100), 1)
+    }
+
+    #[test]
+    fn test_match_all_occurrences() {
+        let template = pattern(16);
+        let screen = screen_with(&[(10, 20, &template), (70, 60, &template)]);
+        let find_builder = FindBuilder::new().acceptable(0.9).max_try_times(1).build();
+        let matches = match_template(&screen, &template, &find_builder);
+        let mut rects: Vec<Rect> = matches.iter().map(|info| info.rect).collect();
+        rects.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+        assert_eq!(
+            rects,
+            vec![Rect::new(10, 20, 16, 16), Rect::new(70, 60, 16, 16)]
+        );
+        assert!(matches.iter().all(|info| info.score.unwrap() > 0.99));
+    }
+
+    #[test]
+    fn test_match_smaller_scale() {
+        let template = pattern(32);
+        let small =
+            image::imageops::resize(&template, 16, 16, image::imageops::FilterType::Triangle);
+        let screen = screen_with(&[(40, 30, &small)]);
+        let find_builder = FindBuilder::new()
+            .acceptable(0.9)
+            .max_try_times(2)
+            .scale_ration(0.5)
+            .build();
+        let matches = match_template(&screen, &template, &find_builder);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(40, 30, 16, 16));
+    }
+
+    #[test]
+    fn test_match_sampled_screenshot() {
+        let template = pattern(16);
+        let full = screen_with(&[(20, 40, &template)]);
+        let image = full.to_image();
+        let sampled = image::imageops::resize(&image, 60, 50, image::imageops::FilterType::Nearest);
+        let screen = Screenshot::from_image(&sampled, Rect::new(0, 0, 120, 100), 2);
+        let find_builder = FindBuilder::new().acceptable(0.9).max_try_times(1).build();
+        let matches = match_template(&screen, &template, &find_builder);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(20, 40, 16, 16));
+    }
+
+    #[test]
+    fn test_match_in_region() {
+        // 区域外有更好的结果时,只在区域截图上查找仍然能找到区域内的目标
+        let template = pattern(16);
+        let mut faded = template.clone();
+        for pixel in faded.pixels_mut() {
+            pixel[2] = pixel[2].saturating_add(30);
+        }
+        let screen = screen_with(&[(10, 10, &template), (80, 60, &faded)]);
+        let find_builder = FindBuilder::new().acceptable(0.8).max_try_times(1).build();
+        let best = &match_template(&screen, &template, &find_builder)[0];
+        assert_eq!(best.rect, Rect::new(10, 10, 16, 16));
+        let region = screen.crop(Rect::new(60, 40, 60, 60), 1);
+        let matches = match_template(&region, &template, &find_builder);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(80, 60, 16, 16));
+    }
+
+    #[test]
+    fn test_match_through_pyramid() {
+        // 64 像素的模板先在缩小 8 倍的图上查找,再逐层细化到奇数坐标
+        let template = pattern(64);
+        let mut screen = image::RgbImage::from_fn(300, 500, |x, y| {
+            image::Rgb([(x * 7 % 200) as u8, (y * 3 % 200) as u8, 100])
+        });
+        image::imageops::overlay(&mut screen, &template, 123, 357);
+        let screen = Screenshot::from_image(&screen, Rect::new(0, 0, 300, 500), 1);
+        assert_eq!(Pyramid::coarse_factor(&GrayImage::from_image(&template)), 8);
+        let find_builder = FindBuilder::new().acceptable(0.95).max_try_times(1).build();
+        let matches = match_template(&screen, &template, &find_builder);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].rect, Rect::new(123, 357, 64, 64));
+        assert!(matches[0].score.unwrap() > 0.99);
+    }
+
+    #[test]
+    fn test_best_candidate_below_threshold() {
+        let template = pattern(16);
+        let mut noisy = template.clone();
+        for (x, y, pixel) in noisy.enumerate_pixels_mut() {
+            if (x + y) % 3 == 0 {
+                pixel[1] = 255;
+            }
+        }
+        let screen = screen_with(&[(50, 40, &noisy)]);
+        let find_builder = FindBuilder::new().acceptable(0.95).max_try_times(1).build();
+        assert!(match_template(&screen, &template, &find_builder).is_empty());
+        let best = &match_candidates(&screen, &template, &find_builder)[0];
+        assert_eq!(best.rect, Rect::new(50, 40, 16, 16));
+        assert!(best.score.unwrap() < 0.95);
+    }
+
+    #[test]
+    fn test_flat_template_never_matches() {
+        let flat = image::RgbImage::from_pixel(8, 8, image::Rgb([128, 128, 128]));
+        let screen = screen_with(&[]);
+        assert_eq!(
+            screen.pixel_at((5, 5).into()),
+            Some(Color::new(128, 128, 128))
+        );
+        assert!(match_template(&screen, &flat, &FindBuilder::new()).is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::entity::MatchInfo;
+
+/// 交并比超过该值的两个结果视为同一目标
+pub const MAX_OVERLAP: f64 = 0.3;
+
+/// 非极大值抑制:按相似度从高到低保留结果,丢弃与已保留结果重叠的候选
+pub fn non_max_suppression(mut matches: Vec<MatchInfo>, max_overlap: f64) -> Vec<MatchInfo> {
+    matches.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut kept: Vec<MatchInfo> = Vec::with_capacity(matches.len());
+    for candidate in matches {
+        if kept
+            .iter()
+            .all(|info| info.rect.iou(&candidate.rect) <= max_overlap)
+        {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Rect;
+
+    fn info(x: i32, y: i32, score: f32) -> MatchInfo {
+        MatchInfo {
+            rect: Rect::new(x, y, 100, 100),
+            score: Some(score),
+        }
+    }
+
+    #[test]
+    fn test_non_max_suppression() {
+        let matches = vec![
+            info(0, 0, 0.85),
+            info(10, 10, 0.95),
+            info(300, 0, 0.9),
+            info(0, 300, 0.8),
+            info(305, 5, 0.82),
+        ];
+        let kept = non_max_suppression(matches, MAX_OVERLAP);
+        let origins: Vec<(f64, f64)> = kept.iter().map(|m| (m.rect.x, m.rect.y)).collect();
+        assert_eq!(origins, vec![(10.0, 10.0), (300.0, 0.0), (0.0, 300.0)]);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::entity::TextInfo;
+use crate::error::Error;
+use crate::geometry::Rect;
+use crate::r#type::MessageType;
+use crate::zx_touch::ZxTouch;
+
+/// `TextRecognizer` 的子任务:识别区域内的文字
+const RECOGNIZE: i32 = 1;
+
+/// 识别文字的消息,`region` 为设备竖屏像素。
+/// 参数依次为区域、自定义词、最小文字高度、识别等级、语言、自动纠错和调试图片路径,未使用的留空
+pub(crate) fn recognize_message(region: Rect, recognition_level: i32) -> String {
+    let message_type: u8 = MessageType::TextRecognizer.into();
+    format!(
+        "{}{};;{};;{};;{};;{};;;;;;{};;;;0;;\r\n",
+        message_type,
+        RECOGNIZE,
+        region.x.round() as i32,
+        region.y.round() as i32,
+        region.width.round() as i32,
+        region.height.round() as i32,
+        recognition_level
+    )
+}
+
+/// 解析识别结果,格式为 `0;;<文字>||x||y||宽||高;;...`,位置为设备竖屏像素,可以省略。
+/// 这个格式还没有和插件的实际输出核对过
+pub(crate) fn parse_text_results(msg: &str) -> Result<Vec<TextInfo>, Error> {
+    let mut items = msg.trim().split(";;");
+    if items.next() != Some("0") {
+        return Err(Error::Err(format!(
+            "text recognition error: {}",
+            msg.trim()
+        )));
+    }
+    Ok(items
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let mut fields = item.split("||");
+            let text = fields.next().unwrap_or_default().to_string();
+            let numbers: Option<Vec<f64>> = fields.map(|field| field.trim().parse().ok()).collect();
+            let rect = match numbers.as_deref() {
+                Some(&[x, y, width, height]) => Some(Rect::new(x, y, width, height)),
+                _ => None,
+            };
+            TextInfo { text, rect }
+        })
+        .collect())
+}
+
+impl ZxTouch {
+    /// 识别区域内的文字,`region` 和结果的位置都使用当前坐标系。
+    /// 请求和结果格式确认之前只在 `experimental` feature 下提供
+    pub async fn recognize_text(
+        &self,
+        region: Rect,
+        recognition_level: i32,
+    ) -> Result<Vec<TextInfo>, Error> {
+        let region = self.to_device_rect(region).await?;
+        let msg = self
+            .request(&recognize_message(region, recognition_level))
+            .await?;
+        let mut results = parse_text_results(&msg)?;
+        for result in results.iter_mut() {
+            if let Some(rect) = result.rect {
+                result.rect = Some(self.from_device_rect(rect).await?);
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize_message() {
+        assert_eq!(
+            recognize_message(Rect::new(10, 20.4, 300, 40), 0),
+            "271;;10;;20;;300;;40;;;;;;0;;;;0;;\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_text_results() {
+        let results = parse_text_results("0;;设置||10||20||80||30;;Wi-Fi").unwrap();
+        assert_eq!(
+            results,
+            vec![
+                TextInfo {
+                    text: "设置".to_string(),
+                    rect: Some(Rect::new(10, 20, 80, 30)),
+                },
+                TextInfo {
+                    text: "Wi-Fi".to_string(),
+                    rect: None,
+                },
+            ]
+        );
+        assert!(parse_text_results("0").unwrap().is_empty());
+        assert!(parse_text_results("-1;;error").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 查找、点击等操作的编号。开启 `debug-print` 时操作内的日志都在以该编号命名的 span 中,
+/// 调试截图的文件名也包含它,便于把截图和日志对应起来
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationId(pub u64);
+
+unsafe impl Send for OperationId {}
+unsafe impl Sync for OperationId {}
+
+impl OperationId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl std::fmt::Display for OperationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "op{}", self.0)
+    }
+}
+
+/// 在操作的 span 中执行 `future`,未开启 `debug-print` 时直接执行
+pub(crate) async fn traced<F: Future>(id: OperationId, kind: &'static str, future: F) -> F::Output {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "debug-print")] {
+            use tracing::Instrument;
+            future
+                .instrument(tracing::debug_span!("operation", id = id.0, kind))
+                .await
+        } else {
+            let _ = (id, kind);
+            future.await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_id() {
+        let a = OperationId::next();
+        let b = OperationId::next();
+        assert!(b.0 > a.0);
+        assert_eq!(OperationId(7).to_string(), "op7");
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// SplitMix64 伪随机数,相同的种子产生相同的序列
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+unsafe impl Send for Rng {}
+unsafe impl Sync for Rng {}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    /// 以当前时间为种子
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        Self::new(nanos as u64)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// [0, 1) 内均匀分布
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /// [low, high) 内均匀分布
+    pub fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+    /// 标准正态分布(Box-Muller)
+    pub fn gaussian(&mut self) -> f64 {
+        let u = 1.0 - self.next_f64();
+        let v = self.next_f64();
+        (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+    /// 半径为 `radius` 的圆内均匀分布的偏移
+    pub fn in_circle(&mut self, radius: f64) -> (f64, f64) {
+        let r = radius * self.next_f64().sqrt();
+        let angle = self.range(0.0, std::f64::consts::TAU);
+        (r * angle.cos(), r * angle.sin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let first: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..4).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first[0], Rng::new(43).next_u64());
+        assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+        for _ in 0..1000 {
+            let x = a.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&x));
+            let (dx, dy) = a.in_circle(5.0);
+            assert!(dx.hypot(dy) <= 5.0);
+        }
+    }
+
+    #[test]
+    fn test_gaussian() {
+        let mut rng = Rng::new(7);
+        let samples: Vec<f64> = (0..10000).map(|_| rng.gaussian()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.05);
+        assert!((variance - 1.0).abs() < 0.1);
+        assert!(samples.iter().all(|x| x.is_finite()));
+    }
+}
@@ -0,0 +1,411 @@
+use crate::entity::Color;
+use crate::error::Error;
+use crate::geometry::{Point, Rect, Size};
+use crate::template::shell_succeeded;
+use crate::zx_touch::ZxTouch;
+
+/// 设备上保存截图的路径
+pub const CAPTURE_PATH: &str = "/var/mobile/Library/ZXTouch/capture.png";
+/// 保存截图时记录区域和采样间隔的 png 文本块关键字,内容为 `x,y,width,height,step`
+const GEOMETRY_KEYWORD: &str = "zxtouch";
+
+fn format_geometry(rect: Rect, step: u32) -> String {
+    format!(
+        "{},{},{},{},{}",
+        rect.x, rect.y, rect.width, rect.height, step
+    )
+}
+
+fn parse_geometry(text: &str) -> Option<(Rect, u32)> {
+    let values: Vec<&str> = text.split(',').collect();
+    let [x, y, width, height, step] = values.as_slice() else {
+        return None;
+    };
+    let number = |value: &str| value.trim().parse::<f64>().ok();
+    let rect = Rect::new(number(x)?, number(y)?, number(width)?, number(height)?);
+    Some((rect, step.trim().parse().ok()?))
+}
+
+/// 屏幕截图,坐标为设备竖屏像素。
+/// 按 `step` 间隔采样,`pixels` 按行存放 `width × height` 个采样点
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screenshot {
+    /// 截图区域
+    pub rect: Rect,
+    pub step: u32,
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+}
+
+unsafe impl Send for Screenshot {}
+unsafe impl Sync for Screenshot {}
+
+impl Screenshot {
+    /// 采样点在设备上的位置
+    pub(crate) fn sample_points(rect: Rect, step: u32) -> (u32, u32, Vec<Point>) {
+        let step = step.max(1) as f64;
+        let width = (rect.width / step).ceil().max(0.0) as u32;
+        let height = (rect.height / step).ceil().max(0.0) as u32;
+        let points = (0..height)
+            .flat_map(|row| {
+                (0..width).map(move |column| {
+                    Point::new(rect.x + column as f64 * step, rect.y + row as f64 * step)
+                })
+            })
+            .collect();
+        (width, height, points)
+    }
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+    /// 第 `row` 行第 `column` 列的采样点
+    pub fn get(&self, column: u32, row: u32) -> Option<Color> {
+        if column >= self.width || row >= self.height {
+            return None;
+        }
+        self.pixels
+            .get((row * self.width + column) as usize)
+            .copied()
+    }
+    /// 设备像素坐标处最近的采样点
+    pub fn pixel_at(&self, point: Point) -> Option<Color> {
+        if !self.rect.contains(point) {
+            return None;
+        }
+        let step = self.step.max(1) as f64;
+        self.get(
+            ((point.x - self.rect.x) / step) as u32,
+            ((point.y - self.rect.y) / step) as u32,
+        )
+    }
+    /// 按行查找第一个与 `color` 各通道差值不超过 `tolerance` 的采样点,返回设备像素坐标
+    pub fn find_color(&self, color: Color, tolerance: u8) -> Option<Point> {
+        let index = self
+            .pixels
+            .iter()
+            .position(|pixel| pixel.distance(color) <= tolerance)? as u32;
+        let step = self.step.max(1) as f64;
+        Some(Point::new(
+            self.rect.x + (index % self.width) as f64 * step,
+            self.rect.y + (index / self.width) as f64 * step,
+        ))
+    }
+    /// 按 `step` 间隔从截图中取出 `rect` 区域,超出截图的部分被裁掉
+    pub fn crop(&self, rect: Rect, step: u32) -> Screenshot {
+        let rect = self
+            .rect
+            .intersect(&rect)
+            .unwrap_or_else(|| Rect::from_origin_size(rect.origin(), Size::default()));
+        let (width, height, points) = Screenshot::sample_points(rect, step);
+        Screenshot {
+            rect,
+            step: step.max(1),
+            width,
+            height,
+            pixels: points
+                .iter()
+                .map(|point| self.pixel_at(*point).unwrap_or_default())
+                .collect(),
+        }
+    }
+    /// 解码 png,`rect` 为图片对应的设备区域
+    pub fn from_png(data: &[u8], rect: Rect, step: u32) -> Result<Self, Error> {
+        let (screenshot, _) = Self::decode_png(data, rect, step)?;
+        Ok(screenshot)
+    }
+    /// 解码 png,同时返回 `to_png` 写入的区域和采样间隔
+    fn decode_png(
+        data: &[u8],
+        rect: Rect,
+        step: u32,
+    ) -> Result<(Self, Option<(Rect, u32)>), Error> {
+        let invalid = |e: png::DecodingError| Error::Err(format!("invalid png: {}", e));
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(data));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(invalid)?;
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap_or_default()];
+        let info = reader.next_frame(&mut buffer).map_err(invalid)?;
+        let channels = info.color_type.samples();
+        let pixels = buffer[..info.buffer_size()]
+            .chunks(info.line_size)
+            .flat_map(|line| line[..info.width as usize * channels].chunks(channels))
+            .map(|pixel| match pixel.len() {
+                1 | 2 => Color::new(pixel[0], pixel[0], pixel[0]),
+                _ => Color::new(pixel[0], pixel[1], pixel[2]),
+            })
+            .collect();
+        let geometry = reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == GEOMETRY_KEYWORD)
+            .and_then(|chunk| parse_geometry(&chunk.text));
+        let screenshot = Self {
+            rect,
+            step,
+            width: info.width,
+            height: info.height,
+            pixels,
+        };
+        Ok((screenshot, geometry))
+    }
+    /// 编码为 png
+    pub fn to_png(&self) -> Result<Vec<u8>, Error> {
+        let invalid = |e: png::EncodingError| Error::Err(e.to_string());
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .add_text_chunk(
+                GEOMETRY_KEYWORD.to_string(),
+                format_geometry(self.rect, self.step),
+            )
+            .map_err(invalid)?;
+        let mut writer = encoder.write_header().map_err(invalid)?;
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect();
+        writer.write_image_data(&data).map_err(invalid)?;
+        writer.finish().map_err(invalid)?;
+        Ok(png)
+    }
+    /// 保存为 png,截图区域和采样间隔写在 png 的文本块中
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+        std::fs::write(path, self.to_png()?)?;
+        Ok(())
+    }
+    /// 加载 `save` 保存的截图,恢复截图区域和采样间隔。
+    /// 没有记录这些信息的 png 视为从原点开始的全分辨率截图
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let data = std::fs::read(path)?;
+        let (mut screenshot, geometry) = Self::decode_png(&data, Rect::default(), 1)?;
+        let (rect, step) = geometry.unwrap_or_else(|| {
+            let size = screenshot.size();
+            (Rect::from_origin_size(Point::default(), size), 1)
+        });
+        let (width, height, _) = Self::sample_points(rect, step);
+        if (width, height) != (screenshot.width, screenshot.height) {
+            return Err(Error::Err(format!(
+                "screenshot {}x{} does not match region {:?} with step {}",
+                screenshot.width, screenshot.height, rect, step
+            )));
+        }
+        screenshot.rect = rect;
+        screenshot.step = step.max(1);
+        Ok(screenshot)
+    }
+    /// 颜色差值超过 `tolerance` 的采样点所占的比例,区域或采样间隔不同时返回 1.0
+    pub fn difference(&self, other: &Screenshot, tolerance: u8) -> f64 {
+        if (self.rect, self.step, self.width, self.height)
+            != (other.rect, other.step, other.width, other.height)
+        {
+            return 1.0;
+        }
+        if self.pixels.is_empty() {
+            return 0.0;
+        }
+        let changed = self
+            .pixels
+            .iter()
+            .zip(&other.pixels)
+            .filter(|(a, b)| a.distance(**b) > tolerance)
+            .count();
+        changed as f64 / self.pixels.len() as f64
+    }
+    /// 内容的 FNV-1a 哈希,用于快速判断两张截图是否相同
+    pub fn checksum(&self) -> u64 {
+        self.pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+}
+
+#[cfg(feature = "image")]
+impl Screenshot {
+    pub fn to_image(&self) -> image::RgbImage {
+        image::RgbImage::from_fn(self.width, self.height, |column, row| {
+            let color = self.get(column, row).unwrap_or_default();
+            image::Rgb([color.r, color.g, color.b])
+        })
+    }
+    /// 从图片构造,`rect` 为图片对应的设备区域
+    pub fn from_image(image: &image::RgbImage, rect: Rect, step: u32) -> Self {
+        Self {
+            rect,
+            step,
+            width: image.width(),
+            height: image.height(),
+            pixels: image
+                .pixels()
+                .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]))
+                .collect(),
+        }
+    }
+}
+
+impl ZxTouch {
+    /// 用 `set_capture_command` 设置的命令在设备上截取全屏,分块校验后传回主机
+    pub async fn capture(&self) -> Result<Screenshot, Error> {
+        let command = self.capture_command().ok_or_else(|| {
+            Error::Err("no capture command, call set_capture_command first".to_string())
+        })?;
+        let command = command.replace("{path}", CAPTURE_PATH);
+        let result = self.run_shell_command(&command).await?;
+        if !shell_succeeded(&result)? {
+            return Err(Error::Err(format!("capture failed: {}", result.trim())));
+        }
+        let data = self.download_file(CAPTURE_PATH).await?;
+        let size = self.device_screen_size().await?;
+        let screenshot =
+            Screenshot::from_png(&data, Rect::from_origin_size(Point::default(), size), 1)?;
+        if screenshot.size() != size {
+            return Err(Error::Err(format!(
+                "capture size {}x{} differs from screen size {}x{}",
+                screenshot.width, screenshot.height, size.width, size.height
+            )));
+        }
+        Ok(screenshot)
+    }
+    /// 截取整个屏幕,`step` 大于 1 时按间隔缩小
+    pub async fn screenshot(&self, step: u32) -> Result<Screenshot, Error> {
+        let screenshot = self.capture().await?;
+        if step <= 1 {
+            return Ok(screenshot);
+        }
+        Ok(screenshot.crop(screenshot.rect, step))
+    }
+    /// 截取屏幕的一部分,`region` 使用当前坐标系
+    pub async fn screenshot_region(&self, region: Rect, step: u32) -> Result<Screenshot, Error> {
+        let rect = self.to_device_rect(region).await?;
+        self.device_screenshot(rect, step).await
+    }
+    /// 截取设备竖屏像素下的区域
+    pub(crate) async fn device_screenshot(
+        &self,
+        rect: Rect,
+        step: u32,
+    ) -> Result<Screenshot, Error> {
+        Ok(self.capture().await?.crop(rect, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> Screenshot {
+        let rect = Rect::new(100, 200, 40, 30);
+        let (width, height, points) = Screenshot::sample_points(rect, 10);
+        Screenshot {
+            rect,
+            step: 10,
+            width,
+            height,
+            pixels: points
+                .iter()
+                .map(|point| Color::new(point.x as u8, point.y as u8, 0))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sample_points() {
+        let (width, height, points) = Screenshot::sample_points(Rect::new(0, 0, 25, 10), 10);
+        assert_eq!((width, height), (3, 1));
+        assert_eq!(
+            points,
+            vec![Point::new(0, 0), Point::new(10, 0), Point::new(20, 0)]
+        );
+    }
+
+    #[test]
+    fn test_pixel_at() {
+        let screenshot = gradient();
+        assert_eq!(screenshot.size(), Size::new(4, 3));
+        assert_eq!(screenshot.get(1, 2), Some(Color::new(110, 220, 0)));
+        assert_eq!(screenshot.get(4, 0), None);
+        assert_eq!(
+            screenshot.pixel_at(Point::new(125, 215)),
+            Some(Color::new(120, 210, 0))
+        );
+        assert_eq!(screenshot.pixel_at(Point::new(99, 215)), None);
+        let mut changed = screenshot.clone();
+        changed.pixels[0] = Color::new(0, 0, 1);
+        assert_ne!(changed.checksum(), screenshot.checksum());
+    }
+
+    #[test]
+    fn test_difference() {
+        let screenshot = gradient();
+        let mut changed = screenshot.clone();
+        assert_eq!(screenshot.difference(&changed, 0), 0.0);
+        changed.pixels[0] = Color::new(100, 205, 0);
+        changed.pixels[1] = Color::new(150, 200, 0);
+        assert!((screenshot.difference(&changed, 10) - 1.0 / 12.0).abs() < 1e-9);
+        assert!((screenshot.difference(&changed, 0) - 2.0 / 12.0).abs() < 1e-9);
+        assert_eq!(
+            screenshot.difference(&screenshot.crop(screenshot.rect, 20), 0),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_find_color() {
+        let screenshot = gradient();
+        assert_eq!(
+            screenshot.find_color(Color::new(121, 209, 0), 2),
+            Some(Point::new(120, 210))
+        );
+        assert_eq!(screenshot.find_color(Color::new(0, 0, 255), 10), None);
+    }
+
+    #[test]
+    fn test_crop() {
+        let screenshot = gradient();
+        let full = screenshot.crop(Rect::new(0, 0, 1000, 1000), 10);
+        assert_eq!(full, screenshot);
+        let part = screenshot.crop(Rect::new(110, 210, 20, 20), 10);
+        assert_eq!(part.size(), Size::new(2, 2));
+        assert_eq!(part.pixels[0], Color::new(110, 210, 0));
+        assert_eq!(
+            part.pixel_at(Point::new(120, 220)),
+            Some(Color::new(120, 220, 0))
+        );
+        let outside = screenshot.crop(Rect::new(500, 500, 20, 20), 10);
+        assert_eq!(outside.size(), Size::new(0, 0));
+        assert!(outside.pixels.is_empty());
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let screenshot = gradient();
+        let png = screenshot.to_png().unwrap();
+        let decoded = Screenshot::from_png(&png, screenshot.rect, screenshot.step).unwrap();
+        assert_eq!(decoded, screenshot);
+        assert!(Screenshot::from_png(b"not a png", screenshot.rect, 1).is_err());
+    }
+
+    #[test]
+    fn test_geometry() {
+        let rect = Rect::new(100.5, 200, 40, 30);
+        assert_eq!(parse_geometry(&format_geometry(rect, 10)), Some((rect, 10)));
+        assert_eq!(parse_geometry("1,2,3"), None);
+        assert_eq!(parse_geometry("1,2,3,4,x"), None);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_image_round_trip() {
+        let screenshot = gradient();
+        let image = screenshot.to_image();
+        let decoded = Screenshot::from_image(&image, screenshot.rect, screenshot.step);
+        assert_eq!(decoded, screenshot);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::entity::{Color, FindBuilder};
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::screenshot::Screenshot;
+use crate::zx_touch::ZxTouch;
+
+/// 列表滚动方向,`Down` 表示查看下方的内容(手指向上滑)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+unsafe impl Send for ScrollDirection {}
+unsafe impl Sync for ScrollDirection {}
+
+/// 滚动查找的目标
+#[derive(Debug, Clone)]
+pub enum ScrollTarget {
+    /// 设备上的图片路径或模板名
+    Image(String, FindBuilder),
+    /// 在滚动区域内查找与 `color` 各通道差值不超过 `tolerance` 的像素
+    Color { color: Color, tolerance: u8 },
+    /// 在滚动区域内识别文字,找到包含该文字的结果。识别到文字但插件没有返回位置时返回错误。
+    /// 文字识别的格式尚未确认,需要开启 `experimental` feature
+    #[cfg(feature = "experimental")]
+    Text(String),
+}
+
+unsafe impl Send for ScrollTarget {}
+unsafe impl Sync for ScrollTarget {}
+
+/// 滚动查找参数
+#[derive(Debug, Clone)]
+pub struct ScrollBuilder {
+    pub direction: ScrollDirection,
+    pub max_swipes: u32,
+    /// 滚动区域,默认整个屏幕
+    pub area: Option<Rect>,
+    /// 每次滑动的时长(毫秒)
+    pub swipe_duration: u32,
+    /// 滑动后等待惯性滚动停止的时间(毫秒)
+    pub settle: u32,
+    /// 检测列表是否到底以及查找颜色时的截图采样间隔
+    pub step: u32,
+    /// 查找文字时的识别等级
+    #[cfg(feature = "experimental")]
+    pub recognition_level: i32,
+}
+
+unsafe impl Send for ScrollBuilder {}
+unsafe impl Sync for ScrollBuilder {}
+
+impl ScrollBuilder {
+    pub fn new() -> Self {
+        Self {
+            direction: ScrollDirection::Down,
+            max_swipes: 10,
+            area: None,
+            swipe_duration: 300,
+            settle: 500,
+            step: 20,
+            #[cfg(feature = "experimental")]
+            recognition_level: 0,
+        }
+    }
+    pub fn direction(&mut self, direction: ScrollDirection) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+    pub fn max_swipes(&mut self, max_swipes: u32) -> &mut Self {
+        self.max_swipes = max_swipes;
+        self
+    }
+    pub fn area(&mut self, area: Rect) -> &mut Self {
+        self.area = Some(area);
+        self
+    }
+    pub fn swipe_duration(&mut self, swipe_duration: u32) -> &mut Self {
+        self.swipe_duration = swipe_duration;
+        self
+    }
+    pub fn settle(&mut self, settle: u32) -> &mut Self {
+        self.settle = settle;
+        self
+    }
+    pub fn step(&mut self, step: u32) -> &mut Self {
+        self.step = step;
+        self
+    }
+    #[cfg(feature = "experimental")]
+    pub fn recognition_level(&mut self, recognition_level: i32) -> &mut Self {
+        self.recognition_level = recognition_level;
+        self
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for ScrollBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 一次滚动的滑动起点和终点,沿滚动方向滑过区域中间的一半
+pub(crate) fn scroll_swipe(area: Rect, direction: ScrollDirection) -> (Point, Point) {
+    let center = area.center();
+    let (dx, dy) = (area.width / 4.0, area.height / 4.0);
+    match direction {
+        ScrollDirection::Down => (center.offset(0.0, dy), center.offset(0.0, -dy)),
+        ScrollDirection::Up => (center.offset(0.0, -dy), center.offset(0.0, dy)),
+        ScrollDirection::Right => (center.offset(dx, 0.0), center.offset(-dx, 0.0)),
+        ScrollDirection::Left => (center.offset(-dx, 0.0), center.offset(dx, 0.0)),
+    }
+}
+
+impl ZxTouch {
+    /// 一边滚动一边查找,找到时返回目标的位置
+    pub async fn scroll_until(
+        &self,
+        target: &ScrollTarget,
+        direction: ScrollDirection,
+        max_swipes: u32,
+    ) -> Result<Point, Error> {
+        let builder = ScrollBuilder::new()
+            .direction(direction)
+            .max_swipes(max_swipes)
+            .build();
+        self.scroll_until_with(target, builder).await
+    }
+    /// 一边滚动一边查找。滑动后画面没有变化视为已经到底,返回 `Error::NotFound`。
+    /// 每次滑动后只截一次图,同时用于判断是否到底、查找颜色和在滚动区域内查找图片
+    pub async fn scroll_until_with(
+        &self,
+        target: &ScrollTarget,
+        builder: ScrollBuilder,
+    ) -> Result<Point, Error> {
+        let area = match builder.area {
+            Some(area) => area,
+            None => self.screen_area().await?,
+        };
+        let device_area = self.to_device_rect(area).await?;
+        // 在截图上查找图片时,模板在开始滑动前读取一次,之后每次查找都从缓存中取
+        #[cfg(feature = "image")]
+        if let ScrollTarget::Image(image_path, find_builder) = target {
+            if builder.area.is_some() || find_builder.region.is_some() {
+                self.template_image(image_path).await?;
+            }
+        }
+        let (from, to) = scroll_swipe(area, builder.direction);
+        let mut checksum = None;
+        for swipe in 0..=builder.max_swipes {
+            let capture = self.capture().await?;
+            let screenshot = capture.crop(device_area, builder.step);
+            if checksum == Some(screenshot.checksum()) {
+                return Err(Error::NotFound(format!(
+                    "{:?} (end of list after {} swipes)",
+                    target, swipe
+                )));
+            }
+            checksum = Some(screenshot.checksum());
+            if let Some(point) = self
+                .locate_target(target, &builder, &capture, &screenshot)
+                .await?
+            {
+                return Ok(point);
+            }
+            if swipe == builder.max_swipes {
+                break;
+            }
+            self.swipe(from, to, builder.swipe_duration).await?;
+            self.delay(builder.settle).await;
+        }
+        Err(Error::NotFound(format!(
+            "{:?} (after {} swipes)",
+            target, builder.max_swipes
+        )))
+    }
+    /// 当前坐标系下的整个屏幕
+    async fn screen_area(&self) -> Result<Rect, Error> {
+        let size = self.device_screen_size().await?;
+        self.from_device_rect(Rect::from_origin_size(Point::default(), size))
+            .await
+    }
+    /// 在滚动区域内查找目标,`capture` 为本次滑动后的全屏截图,`screenshot` 为按 `step` 采样的区域截图
+    async fn locate_target(
+        &self,
+        target: &ScrollTarget,
+        builder: &ScrollBuilder,
+        _capture: &Screenshot,
+        screenshot: &Screenshot,
+    ) -> Result<Option<Point>, Error> {
+        match target {
+            ScrollTarget::Image(image_path, find_builder) => {
+                let mut find_builder = find_builder.clone();
+                if let (Some(area), None) = (builder.area, find_builder.region) {
+                    find_builder.region(area);
+                }
+                // 有查找区域时在本次截图上查找,不再另外截图
+                #[cfg(feature = "image")]
+                if let Some(region) = find_builder.region {
+                    let template = self.template_image(image_path).await?;
+                    let region = _capture.crop(self.to_device_rect(region).await?, 1);
+                    let found = self
+                        .match_screenshot(&region, &template, &find_builder)
+                        .await?;
+                    return Ok(found.first().map(|info| info.center()));
+                }
+                // 全屏查找时使用设备端查找;未开启 `image` feature 时只接受中心点在滚动区域内的结果
+                let (info, _) = self.locate_image(image_path, &find_builder).await?;
+                Ok(info.map(|info| info.center()))
+            }
+            ScrollTarget::Color { color, tolerance } => {
+                match screenshot.find_color(*color, *tolerance) {
+                    Some(point) => Ok(Some(self.from_device(point).await?)),
+                    None => Ok(None),
+                }
+            }
+            #[cfg(feature = "experimental")]
+            ScrollTarget::Text(text) => {
+                let area = self.from_device_rect(screenshot.rect).await?;
+                let results = self.recognize_text(area, builder.recognition_level).await?;
+                match results
+                    .into_iter()
+                    .find(|result| result.text.contains(text.as_str()))
+                {
+                    Some(crate::entity::TextInfo {
+                        rect: Some(rect), ..
+                    }) => Ok(Some(rect.center())),
+                    Some(_) => Err(Error::Err(format!(
+                        "text {:?} recognized without a position",
+                        text
+                    ))),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scroll_swipe() {
+        let area = Rect::new(0, 200, 1000, 800);
+        assert_eq!(
+            scroll_swipe(area, ScrollDirection::Down),
+            (Point::new(500, 800), Point::new(500, 400))
+        );
+        assert_eq!(
+            scroll_swipe(area, ScrollDirection::Up),
+            (Point::new(500, 400), Point::new(500, 800))
+        );
+        assert_eq!(
+            scroll_swipe(area, ScrollDirection::Right),
+            (Point::new(750, 600), Point::new(250, 600))
+        );
+        assert_eq!(
+            scroll_swipe(area, ScrollDirection::Left),
+            (Point::new(250, 600), Point::new(750, 600))
+        );
+    }
+}
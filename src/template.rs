@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::coordinate::ScreenMetrics;
+use crate::debug;
+use crate::entity::{FindBuilder, MatchInfo};
+use crate::error::Error;
+use crate::geometry::Size;
+use crate::zx_touch::ZxTouch;
+
+/// 设备上存放上传模板的目录
+pub const TEMPLATE_CACHE_DIR: &str = "/var/mobile/Library/ZXTouch/templates";
+
+/// 每条 shell 命令写入的字节数,转义成八进制后约 64KB,与下载时每块的消息长度相当
+const UPLOAD_CHUNK: usize = 16 * 1024;
+
+/// 主机上的模板图片
+#[derive(Debug, Clone)]
+pub enum TemplateImage {
+    /// 本地文件路径
+    File(PathBuf),
+    /// 图片内容及扩展名(如 `png`)
+    Bytes { data: Vec<u8>, extension: String },
+}
+
+unsafe impl Send for TemplateImage {}
+unsafe impl Sync for TemplateImage {}
+
+impl TemplateImage {
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        TemplateImage::File(path.into())
+    }
+    pub fn bytes(data: impl Into<Vec<u8>>, extension: &str) -> Self {
+        TemplateImage::Bytes {
+            data: data.into(),
+            extension: extension.to_string(),
+        }
+    }
+    /// 读取图片内容和扩展名。扩展名会拼进设备上的路径,只允许字母和数字
+    pub(crate) fn load(&self) -> Result<(Vec<u8>, String), Error> {
+        let (data, extension) = match self {
+            TemplateImage::File(path) => {
+                let data = std::fs::read(path)?;
+                let extension = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .unwrap_or("png")
+                    .to_lowercase();
+                (data, extension)
+            }
+            TemplateImage::Bytes { data, extension } => (data.clone(), extension.clone()),
+        };
+        if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(Error::Err(format!(
+                "invalid template extension: {:?}",
+                extension
+            )));
+        }
+        Ok((data, extension))
+    }
+}
+
+/// FNV-1a 64 位哈希,只用作缓存文件名
+pub(crate) fn content_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// 用单引号包住参数,内部的单引号写成 `'\''`,shell 不会再解释其中的任何字符
+pub(crate) fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// 模板在设备上的缓存路径,内容相同的图片共用一个文件
+pub(crate) fn cache_path(data: &[u8], extension: &str) -> String {
+    format!(
+        "{}/{:016x}.{}",
+        TEMPLATE_CACHE_DIR,
+        content_hash(data),
+        extension
+    )
+}
+
+/// 把文件内容拆成若干条 shell 命令:先写入临时文件,全部写完后再改名,避免留下不完整的模板
+pub(crate) fn upload_commands(data: &[u8], path: &str) -> Vec<String> {
+    let temp = shell_quote(&format!("{}.part", path));
+    let path = shell_quote(path);
+    let mut commands = vec![format!(
+        "mkdir -p {} && rm -f {}",
+        shell_quote(TEMPLATE_CACHE_DIR),
+        temp
+    )];
+    for chunk in data.chunks(UPLOAD_CHUNK) {
+        let escaped: String = chunk.iter().map(|byte| format!("\\{:03o}", byte)).collect();
+        commands.push(format!("printf '{}' >> {}", escaped, temp));
+    }
+    commands.push(format!("touch {} && mv -f {} {}", temp, temp, path));
+    commands
+}
+
+/// 解析 RunShell 的返回值,判断命令是否成功。
+///
+/// 这里假定返回值以命令的退出码开头、`;;` 之后是输出(如 `0;;` 或 `1;;`),这是按插件的消息格式推断的,
+/// 没有在插件源码中确认。`test -f` 依赖这一点区分文件是否存在,返回值不是这种格式时报错而不是当作成功
+pub(crate) fn shell_succeeded(reply: &str) -> Result<bool, Error> {
+    reply
+        .split(";;")
+        .next()
+        .and_then(|code| code.trim().parse::<i32>().ok())
+        .map(|code| code == 0)
+        .ok_or_else(|| Error::Err(format!("invalid shell reply: {:.128}", reply.trim())))
+}
+
+/// 同一模板针对某种设备的图片
+#[derive(Debug, Clone)]
+pub struct TemplateVariant {
+    /// 文件名为 `1170x2532` 时对应的屏幕像素大小
+    pub size: Option<Size>,
+    /// 文件名为 `3x` 或 `@3x` 时对应的屏幕缩放比例
+    pub scale: Option<i32>,
+    pub path: PathBuf,
+}
+
+unsafe impl Send for TemplateVariant {}
+unsafe impl Sync for TemplateVariant {}
+
+impl TemplateVariant {
+    /// 按文件名解析适用的设备,无法识别的文件名作为通用图片
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let size = stem.split_once('x').and_then(|(width, height)| {
+            Some(Size::new(
+                width.parse::<u32>().ok()?,
+                height.parse::<u32>().ok()?,
+            ))
+        });
+        let scale = stem
+            .trim_start_matches('@')
+            .strip_suffix('x')
+            .and_then(|scale| scale.parse::<i32>().ok());
+        Self { size, scale, path }
+    }
+    /// 与屏幕的匹配程度,越小越合适
+    fn rank(&self, screen: &ScreenMetrics) -> (u8, f64) {
+        let screen_scale = screen.scale.round() as i32;
+        match (self.size, self.scale) {
+            (Some(size), _) if size == screen.size => (0, 0.0),
+            (None, Some(scale)) if scale == screen_scale => (1, 0.0),
+            (None, None) => (2, 0.0),
+            (Some(size), _) => (
+                3,
+                (size.width - screen.size.width).abs() + (size.height - screen.size.height).abs(),
+            ),
+            (None, Some(scale)) => (4, (scale - screen_scale).abs() as f64),
+        }
+    }
+}
+
+/// 按名称管理模板图片,目录结构为 `<root>/<模板名>/<设备>.png`
+#[derive(Debug, Clone, Default)]
+pub struct TemplateRegistry {
+    templates: HashMap<String, Vec<TemplateVariant>>,
+}
+
+unsafe impl Send for TemplateRegistry {}
+unsafe impl Sync for TemplateRegistry {}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 加载目录下的所有模板,每个子目录是一个模板
+    pub fn load(root: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut registry = Self::new();
+        for entry in std::fs::read_dir(root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            for file in std::fs::read_dir(entry.path())? {
+                let file = file?;
+                if file.file_type()?.is_file() {
+                    registry.insert(&name, TemplateVariant::from_path(file.path()));
+                }
+            }
+        }
+        Ok(registry)
+    }
+    pub fn insert(&mut self, name: &str, variant: TemplateVariant) {
+        self.templates
+            .entry(name.to_string())
+            .or_default()
+            .push(variant);
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.keys().map(|name| name.as_str()).collect()
+    }
+    /// 为当前屏幕挑选最合适的图片:屏幕大小相同 > 缩放比例相同 > 通用图片 > 最接近的尺寸
+    pub fn select(&self, name: &str, screen: &ScreenMetrics) -> Option<&TemplateVariant> {
+        self.templates.get(name)?.iter().min_by(|a, b| {
+            a.rank(screen)
+                .partial_cmp(&b.rank(screen))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+    }
+}
+
+impl ZxTouch {
+    /// 模板库中适合当前屏幕的图片
+    async fn select_template(&self, name: &str) -> Result<TemplateImage, Error> {
+        let screen = self.screen_metrics().await?;
+        let variant = self
+            .template_registry()
+            .select(name, &screen)
+            .cloned()
+            .ok_or_else(|| Error::Err(format!("template {} has no image", name)))?;
+        Ok(TemplateImage::File(variant.path))
+    }
+    /// 把模板名换算成设备上的图片路径,未注册的名称按设备路径原样返回
+    pub(crate) async fn resolve_template(&self, name: &str) -> Result<String, Error> {
+        if !self.template_registry().contains(name) {
+            return Ok(name.to_string());
+        }
+        let template = self.select_template(name).await?;
+        self.upload_template(&template).await
+    }
+    /// 解码后的模板图片,用于在主机上查找。模板库中的模板读取本地文件,其他名称按设备路径从设备读取。
+    /// 结果按名称缓存,重新连接时清空
+    #[cfg(feature = "image")]
+    pub(crate) async fn template_image(
+        &self,
+        name: &str,
+    ) -> Result<std::sync::Arc<image::RgbImage>, Error> {
+        if let Some(template) = self.decoded_templates.lock().unwrap().get(name) {
+            return Ok(template.clone());
+        }
+        let data = if self.template_registry().contains(name) {
+            self.select_template(name).await?.load()?.0
+        } else {
+            self.download_file(name).await?
+        };
+        let template = std::sync::Arc::new(crate::matcher::decode_template(&data)?);
+        self.decoded_templates
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), template.clone());
+        Ok(template)
+    }
+    /// 上传模板图片到设备缓存目录,返回设备上的路径。路径由内容哈希决定,
+    /// 设备上已有该文件时(例如之前的连接上传过)不再上传
+    pub async fn upload_template(&self, template: &TemplateImage) -> Result<String, Error> {
+        let (data, extension) = template.load()?;
+        let path = cache_path(&data, &extension);
+        if self.uploaded_templates.lock().unwrap().contains(&path) {
+            return Ok(path);
+        }
+        // 上传时先写临时文件再改名,文件存在就一定是完整的
+        let exists = self
+            .run_shell_command(&format!("test -f {}", shell_quote(&path)))
+            .await?;
+        if shell_succeeded(&exists)? {
+            debug!("template already on device: {}", path);
+            self.uploaded_templates.lock().unwrap().insert(path.clone());
+            return Ok(path);
+        }
+        for command in upload_commands(&data, &path) {
+            let result = self.run_shell_command(&command).await?;
+            if !shell_succeeded(&result)? {
+                return Err(Error::Err(format!(
+                    "upload template failed: {}",
+                    result.trim()
+                )));
+            }
+        }
+        self.uploaded_templates.lock().unwrap().insert(path.clone());
+        Ok(path)
+    }
+    /// 使用主机上的模板图片查找
+    pub async fn find_template(
+        &self,
+        template: &TemplateImage,
+        find_builder: FindBuilder,
+    ) -> Result<Option<MatchInfo>, Error> {
+        let path = self.upload_template(template).await?;
+        self.image_find(&path, find_builder).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_path() {
+        assert_eq!(content_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(content_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(cache_path(b"a", "png"), cache_path(b"a", "png"));
+        assert_ne!(cache_path(b"a", "png"), cache_path(b"b", "png"));
+        assert!(cache_path(b"a", "jpg").starts_with(TEMPLATE_CACHE_DIR));
+    }
+
+    fn screen() -> ScreenMetrics {
+        ScreenMetrics {
+            size: Size::new(1170, 2532),
+            scale: 3.0,
+        }
+    }
+
+    #[test]
+    fn test_variant_from_path() {
+        let variant = TemplateVariant::from_path("assets/login/1170x2532.png");
+        assert_eq!(variant.size, Some(Size::new(1170, 2532)));
+        assert_eq!(variant.scale, None);
+        assert_eq!(TemplateVariant::from_path("a/@2x.png").scale, Some(2));
+        assert_eq!(TemplateVariant::from_path("a/3x.png").scale, Some(3));
+        let default = TemplateVariant::from_path("a/default.png");
+        assert_eq!((default.size, default.scale), (None, None));
+    }
+
+    #[test]
+    fn test_select_variant() {
+        let mut registry = TemplateRegistry::new();
+        for file in ["default.png", "2x.png", "750x1334.png", "1284x2778.png"] {
+            registry.insert("login", TemplateVariant::from_path(file));
+        }
+        let select =
+            |registry: &TemplateRegistry| registry.select("login", &screen()).unwrap().path.clone();
+        assert_eq!(select(&registry), PathBuf::from("default.png"));
+        registry.insert("login", TemplateVariant::from_path("@3x.png"));
+        assert_eq!(select(&registry), PathBuf::from("@3x.png"));
+        registry.insert("login", TemplateVariant::from_path("1170x2532.png"));
+        assert_eq!(select(&registry), PathBuf::from("1170x2532.png"));
+        assert!(registry.select("logout", &screen()).is_none());
+
+        let mut sizes = TemplateRegistry::new();
+        for file in ["750x1334.png", "1284x2778.png"] {
+            sizes.insert("login", TemplateVariant::from_path(file));
+        }
+        assert_eq!(
+            sizes.select("login", &screen()).unwrap().path,
+            PathBuf::from("1284x2778.png")
+        );
+    }
+
+    #[test]
+    fn test_load_registry() {
+        let root = std::env::temp_dir().join(format!("zxtouch-assets-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("login_button")).unwrap();
+        std::fs::write(root.join("login_button/1170x2532.png"), b"png").unwrap();
+        std::fs::write(root.join("readme.txt"), b"ignored").unwrap();
+        let registry = TemplateRegistry::load(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(registry.names(), vec!["login_button"]);
+        assert!(registry.select("login_button", &screen()).is_some());
+    }
+
+    #[test]
+    fn test_upload_commands() {
+        let data: Vec<u8> = (0..=255).cycle().take(UPLOAD_CHUNK + 10).collect();
+        let commands = upload_commands(&data, "/tmp/t.png");
+        assert_eq!(commands.len(), 4);
+        assert!(commands[1].starts_with("printf '\\000\\001\\002"));
+        assert!(commands[1].ends_with("' >> '/tmp/t.png.part'"));
+        assert_eq!(commands[2].matches('\\').count(), 10);
+        assert!(!commands.iter().any(|command| command.contains('\n')));
+        assert_eq!(
+            commands[3],
+            "touch '/tmp/t.png.part' && mv -f '/tmp/t.png.part' '/tmp/t.png'"
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/a b.png"), "'/tmp/a b.png'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote("$(rm -rf /)"), "'$(rm -rf /)'");
+    }
+
+    #[test]
+    fn test_template_extension() {
+        assert_eq!(
+            TemplateImage::bytes(b"a".to_vec(), "png").load().unwrap().1,
+            "png"
+        );
+        assert!(TemplateImage::bytes(b"a".to_vec(), "png; rm -rf /")
+            .load()
+            .is_err());
+        assert!(TemplateImage::bytes(b"a".to_vec(), "").load().is_err());
+    }
+
+    #[test]
+    fn test_shell_succeeded() {
+        assert!(shell_succeeded("0;;\r\n").unwrap());
+        assert!(shell_succeeded("0").unwrap());
+        assert!(!shell_succeeded("1;;\r\n").unwrap());
+        assert!(shell_succeeded("").is_err());
+        assert!(shell_succeeded("ok").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::debug;
+use crate::entity::{TouchFinger, TouchType};
+use crate::error::Error;
+use crate::geometry::Point;
+use crate::gesture::TouchFrame;
+use crate::zx_touch::{perform_touch_message, usleep_message, ZxTouch};
+
+/// 时间轴上的触摸事件,`at` 为相对开始的毫秒数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineEvent {
+    pub at: u32,
+    pub touch_type: TouchType,
+    pub point: Point,
+    pub finger: TouchFinger,
+}
+
+unsafe impl Send for TimelineEvent {}
+unsafe impl Sync for TimelineEvent {}
+
+/// 按时间排列的触摸序列,用于节奏游戏、连招等对时间要求严格的输入
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    pub events: Vec<TimelineEvent>,
+}
+
+unsafe impl Send for Timeline {}
+unsafe impl Sync for Timeline {}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+    pub fn event(
+        &mut self,
+        at: u32,
+        touch_type: TouchType,
+        point: impl Into<Point>,
+        finger: TouchFinger,
+    ) -> &mut Self {
+        self.events.push(TimelineEvent {
+            at,
+            touch_type,
+            point: point.into(),
+            finger,
+        });
+        self
+    }
+    pub fn down(&mut self, at: u32, point: impl Into<Point>, finger: TouchFinger) -> &mut Self {
+        self.event(at, TouchType::Down, point, finger)
+    }
+    pub fn move_to(&mut self, at: u32, point: impl Into<Point>, finger: TouchFinger) -> &mut Self {
+        self.event(at, TouchType::Move, point, finger)
+    }
+    pub fn up(&mut self, at: u32, point: impl Into<Point>, finger: TouchFinger) -> &mut Self {
+        self.event(at, TouchType::Up, point, finger)
+    }
+    /// 在 `at` 按下,按住 `hold` 毫秒后抬起
+    pub fn tap(
+        &mut self,
+        at: u32,
+        point: impl Into<Point>,
+        finger: TouchFinger,
+        hold: u32,
+    ) -> &mut Self {
+        let point = point.into();
+        self.down(at, point, finger).up(at + hold, point, finger)
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+
+    /// 最后一个事件的时间
+    pub fn duration(&self) -> u32 {
+        self.events.iter().map(|event| event.at).max().unwrap_or(0)
+    }
+    /// 按时间排序,同一时刻的事件合并为一帧,保持添加顺序
+    pub fn frames(&self) -> Vec<(u32, TouchFrame)> {
+        let mut events = self.events.clone();
+        events.sort_by_key(|event| event.at);
+        let mut frames: Vec<(u32, TouchFrame)> = Vec::new();
+        for event in events {
+            let item = (event.touch_type, event.point, event.finger);
+            match frames.last_mut() {
+                Some((at, frame)) if *at == event.at => frame.push(item),
+                _ => frames.push((event.at, vec![item])),
+            }
+        }
+        frames
+    }
+}
+
+/// 编译成设备端执行的一串消息:每帧一条批量触摸,帧之间插入 usleep。返回消息和 usleep 的条数
+pub(crate) fn compile(frames: &[(u32, TouchFrame)]) -> (String, usize) {
+    let mut msg = String::new();
+    let mut sleeps = 0;
+    let mut previous = frames.first().map(|(at, _)| *at).unwrap_or(0);
+    for (at, frame) in frames {
+        if *at > previous {
+            msg.push_str(&usleep_message(at - previous));
+            sleeps += 1;
+        }
+        msg.push_str(&perform_touch_message(frame));
+        previous = *at;
+    }
+    (msg, sleeps)
+}
+
+/// 时间轴执行结果,时间单位为毫秒。迟到为估计到达设备的时间(发出时间加单程延迟)减去计划时间
+#[derive(Debug, Clone)]
+pub struct TimelineReport {
+    pub frames: usize,
+    /// 开始前测得的单程延迟(往返时间的一半),每帧按该值提前发出
+    pub latency: f64,
+    pub mean_lateness: f64,
+    pub max_lateness: f64,
+}
+
+unsafe impl Send for TimelineReport {}
+unsafe impl Sync for TimelineReport {}
+
+/// 为了在 `target` 到达设备应该发出的时间
+fn send_at(target: Instant, latency: f64) -> Instant {
+    target
+        .checked_sub(Duration::from_secs_f64(latency.max(0.0) / 1000.0))
+        .unwrap_or(target)
+}
+
+fn millis_between(from: Instant, to: Instant) -> f64 {
+    if to >= from {
+        to.duration_since(from).as_secs_f64() * 1000.0
+    } else {
+        -(from.duration_since(to).as_secs_f64() * 1000.0)
+    }
+}
+
+/// 时间来源,测试中用假时钟代替
+trait Clock {
+    fn now(&self) -> Instant;
+    async fn sleep_until(&self, deadline: Instant);
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    async fn sleep_until(&self, deadline: Instant) {
+        let now = Instant::now();
+        if deadline > now {
+            futures_timer::Delay::new(deadline - now).await;
+        }
+    }
+}
+
+/// 按 `start` 加帧时间减去 `latency` 的时刻依次调用 `send`,返回报告
+async fn pace<C, F, Fut>(
+    clock: &C,
+    start: Instant,
+    latency: f64,
+    frames: &[(u32, TouchFrame)],
+    mut send: F,
+) -> Result<TimelineReport, Error>
+where
+    C: Clock,
+    F: FnMut(TouchFrame) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let (mut total, mut max) = (0.0, f64::MIN);
+    for (at, frame) in frames {
+        let target = start + Duration::from_millis(*at as u64);
+        clock.sleep_until(send_at(target, latency)).await;
+        let sent = clock.now();
+        send(frame.clone()).await?;
+        let lateness = millis_between(target, sent) + latency.max(0.0);
+        total += lateness;
+        max = max.max(lateness);
+    }
+    Ok(TimelineReport {
+        frames: frames.len(),
+        latency,
+        mean_lateness: if frames.is_empty() {
+            0.0
+        } else {
+            total / frames.len() as f64
+        },
+        max_lateness: if frames.is_empty() { 0.0 } else { max },
+    })
+}
+
+impl ZxTouch {
+    /// 测量与设备之间的往返时间(毫秒),取 `samples` 次设备端 0 毫秒睡眠的中位数
+    pub async fn measure_latency(&self, samples: u32) -> Result<f64, Error> {
+        let mut rtts = Vec::with_capacity(samples.max(1) as usize);
+        for _ in 0..samples.max(1) {
+            let start = Instant::now();
+            self.sleep(0).await?;
+            rtts.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+        rtts.sort_by(f64::total_cmp);
+        Ok(rtts[rtts.len() / 2])
+    }
+    /// 转换成设备像素后按时间排好的帧,并检查事件顺序
+    async fn device_frames(&self, timeline: &Timeline) -> Result<Vec<(u32, TouchFrame)>, Error> {
+        let mut frames = timeline.frames();
+        for (_, frame) in frames.iter_mut() {
+            for (_, point, _) in frame.iter_mut() {
+                *point = self.to_device(*point).await?;
+            }
+        }
+        let events: TouchFrame = frames.iter().flat_map(|(_, frame)| frame.clone()).collect();
+        self.validate_touch(&events, self.device_screen_size().await?)?;
+        Ok(frames)
+    }
+    /// 在本地按时间轴发送事件,帧时间相对调用方给出的绝对时刻 `start`。按绝对时间排期,误差不会累积;
+    /// 先测量单程延迟,每帧在 `start + at` 之前提前这么久发出,估计在计划时间到达设备。
+    /// 测量本身需要几次往返,`start` 应留出余量,已经过去的帧会立即发出并计入迟到。等待期间不占用连接
+    pub async fn play_timeline(
+        &self,
+        timeline: &Timeline,
+        start: Instant,
+    ) -> Result<TimelineReport, Error> {
+        let frames = self.device_frames(timeline).await?;
+        let latency = self.measure_latency(3).await? / 2.0;
+        let report = pace(&SystemClock, start, latency, &frames, |frame| {
+            self.send_touch_events(frame)
+        })
+        .await?;
+        debug!("timeline finished: {:?}", report);
+        Ok(report)
+    }
+    /// 把时间轴编译成批量触摸和 usleep 一次性发给设备,由设备控制节奏,不受网络抖动影响。执行期间占用连接
+    pub async fn play_timeline_on_device(&self, timeline: &Timeline) -> Result<(), Error> {
+        let frames = self.device_frames(timeline).await?;
+        let (msg, sleeps) = compile(&frames);
+        let events: TouchFrame = frames.into_iter().flat_map(|(_, frame)| frame).collect();
+        self.send_pipelined(&msg, sleeps, &events).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline() -> Timeline {
+        Timeline::new()
+            .tap(100, (10, 20), TouchFinger::Two, 50)
+            .down(0, (30, 40), TouchFinger::One)
+            .move_to(100, (35, 45), TouchFinger::One)
+            .up(200, (35, 45), TouchFinger::One)
+            .build()
+    }
+
+    #[test]
+    fn test_timeline_frames() {
+        let frames = timeline().frames();
+        let times: Vec<u32> = frames.iter().map(|(at, _)| *at).collect();
+        assert_eq!(times, vec![0, 100, 150, 200]);
+        assert_eq!(
+            frames[1].1,
+            vec![
+                (TouchType::Down, Point::new(10, 20), TouchFinger::Two),
+                (TouchType::Move, Point::new(35, 45), TouchFinger::One),
+            ]
+        );
+        assert_eq!(timeline().duration(), 200);
+        assert_eq!(Timeline::new().duration(), 0);
+    }
+
+    #[test]
+    fn test_compile() {
+        let (msg, sleeps) = compile(&timeline().frames());
+        assert_eq!(sleeps, 3);
+        let lines: Vec<&str> = msg.split_terminator("\r\n").collect();
+        assert_eq!(
+            lines,
+            vec![
+                "1011010030000400",
+                "18100000",
+                "10210200100002002010035000450",
+                "1850000",
+                "1010020010000200",
+                "1850000",
+                "1010010035000450",
+            ]
+        );
+    }
+
+    /// 假时钟:睡眠直接把时间推进到截止时刻
+    struct FakeClock(std::cell::Cell<Instant>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+        async fn sleep_until(&self, deadline: Instant) {
+            if deadline > self.0.get() {
+                self.0.set(deadline);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pace() {
+        let origin = Instant::now();
+        let clock = FakeClock(std::cell::Cell::new(origin));
+        let start = origin + Duration::from_millis(100);
+        let sent = std::cell::RefCell::new(Vec::new());
+        let frames = timeline().frames();
+        let report = futures::executor::block_on(pace(&clock, start, 8.0, &frames, |_| {
+            sent.borrow_mut().push(millis_between(start, clock.now()));
+            async { Ok(()) }
+        }))
+        .unwrap();
+        // 每帧比计划时间早一个单程延迟发出
+        assert_eq!(sent.into_inner(), vec![-8.0, 92.0, 142.0, 192.0]);
+        assert_eq!(report.frames, 4);
+        assert_eq!(report.max_lateness, 0.0);
+
+        // 开始时刻已经过去时立即发出,并计入迟到
+        let report =
+            futures::executor::block_on(pace(&clock, origin, 8.0, &frames[..1], |_| async {
+                Ok(())
+            }))
+            .unwrap();
+        // 假时钟停在上一轮最后一帧的发出时刻 origin + 292ms,再加单程延迟
+        assert_eq!(report.max_lateness, 300.0);
+    }
+
+    #[test]
+    fn test_send_at() {
+        let target = Instant::now() + Duration::from_millis(100);
+        assert_eq!(send_at(target, 2.0), target - Duration::from_millis(2));
+        assert_eq!(send_at(target, -1.0), target);
+        assert_eq!(
+            millis_between(target, target - Duration::from_millis(3)),
+            -3.0
+        );
+    }
+}
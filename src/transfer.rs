@@ -0,0 +1,197 @@
+use crate::debug;
+use crate::error::Error;
+use crate::template::shell_quote;
+use crate::zx_touch::ZxTouch;
+
+/// 每块的字节数,base64 编码后约 64KB
+pub const CHUNK_SIZE: usize = 48 * 1024;
+/// 单块校验失败时最多重新读取的次数
+const MAX_RETRIES: usize = 3;
+
+/// 与 POSIX `cksum` 相同的 CRC,设备端用 `cksum` 计算,主机端用它校验
+pub(crate) fn cksum(data: &[u8]) -> u32 {
+    fn feed(crc: u32, byte: u8) -> u32 {
+        let mut crc = crc ^ ((byte as u32) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    }
+    let mut crc = data.iter().fold(0, |crc, &byte| feed(crc, byte));
+    let mut length = data.len();
+    while length > 0 {
+        crc = feed(crc, (length & 0xff) as u8);
+        length >>= 8;
+    }
+    !crc
+}
+
+/// 解码 base64,忽略空白字符
+pub(crate) fn base64_decode(text: &str) -> Result<Vec<u8>, Error> {
+    fn value(byte: u8) -> Option<u32> {
+        match byte {
+            b'A'..=b'Z' => Some((byte - b'A') as u32),
+            b'a'..=b'z' => Some((byte - b'a') as u32 + 26),
+            b'0'..=b'9' => Some((byte - b'0') as u32 + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let symbols: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    let data = symbols
+        .iter()
+        .rposition(|&byte| byte != b'=')
+        .map_or(&symbols[..0], |end| &symbols[..=end]);
+    if !symbols.len().is_multiple_of(4) || symbols.len() - data.len() > 2 {
+        return Err(Error::Err("invalid base64 length".to_string()));
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for group in data.chunks(4) {
+        let mut buffer = 0u32;
+        for &byte in group {
+            let value = value(byte)
+                .ok_or_else(|| Error::Err(format!("invalid base64: {:?}", byte as char)))?;
+            buffer = buffer << 6 | value;
+        }
+        buffer <<= 6 * (4 - group.len() as u32);
+        let decoded = buffer.to_be_bytes();
+        bytes.extend_from_slice(&decoded[1..group.len()]);
+    }
+    Ok(bytes)
+}
+
+/// 解析 `cksum` 的输出,返回 CRC 和字节数
+fn parse_cksum<'a>(words: &mut impl Iterator<Item = &'a str>) -> Option<(u32, usize)> {
+    let crc = words.next()?.parse().ok()?;
+    let size = words.next()?.parse().ok()?;
+    Some((crc, size))
+}
+
+/// 读取第 `index` 块:先写到临时文件,再输出该块的 `cksum` 和 base64
+fn chunk_command(path: &str, index: usize) -> String {
+    let temp = shell_quote(&chunk_path(path));
+    format!(
+        "dd if={} of={} bs={} skip={} count=1 2>/dev/null && cksum < {} && base64 < {} | tr -d '\\n'",
+        shell_quote(path),
+        temp,
+        CHUNK_SIZE,
+        index,
+        temp,
+        temp
+    )
+}
+
+/// 读取分块时使用的临时文件
+fn chunk_path(path: &str) -> String {
+    format!("{}.chunk", path)
+}
+
+/// 解析并校验一块的输出,格式为 `<crc> <字节数>\n<base64>`
+fn parse_chunk(output: &str) -> Result<Vec<u8>, Error> {
+    let mut words = output.split_whitespace();
+    let (crc, size) = parse_cksum(&mut words)
+        .ok_or_else(|| Error::Err(format!("invalid chunk header: {:.64}", output)))?;
+    let data = base64_decode(&words.collect::<String>())?;
+    if data.len() != size || cksum(&data) != crc {
+        return Err(Error::Err(format!(
+            "chunk checksum mismatch: expected {} bytes crc {}, got {} bytes crc {}",
+            size,
+            crc,
+            data.len(),
+            cksum(&data)
+        )));
+    }
+    Ok(data)
+}
+
+impl ZxTouch {
+    /// 读取设备上的文件。按 `CHUNK_SIZE` 分块用 base64 传输,每块和整个文件都用 `cksum` 校验,
+    /// 单块校验失败时重新读取该块。
+    ///
+    /// 命令的输出通过 `run_shell_output` 读取,依赖插件按 `0;;<输出>` 返回标准输出,
+    /// 格式不符时每块都会校验失败并报错,不会返回错误的内容
+    pub async fn download_file(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let output = self
+            .run_shell_output(&format!("cksum < {}", shell_quote(path)))
+            .await?;
+        let (crc, size) = parse_cksum(&mut output.split_whitespace())
+            .ok_or_else(|| Error::Err(format!("cannot read {}: {}", path, output.trim())))?;
+        let data = self.download_chunks(path, size).await;
+        // 无论成功与否都删除临时文件,删除失败不掩盖下载本身的错误
+        let cleanup = self
+            .run_shell_command(&format!("rm -f {}", shell_quote(&chunk_path(path))))
+            .await;
+        let data = data?;
+        cleanup?;
+        if data.len() != size || cksum(&data) != crc {
+            return Err(Error::Err(format!("{} changed during download", path)));
+        }
+        debug!("downloaded {}: {} bytes", path, size);
+        Ok(data)
+    }
+    async fn download_chunks(&self, path: &str, size: usize) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::with_capacity(size);
+        for index in 0..size.div_ceil(CHUNK_SIZE) {
+            data.extend(self.download_chunk(path, index).await?);
+        }
+        Ok(data)
+    }
+    async fn download_chunk(&self, path: &str, index: usize) -> Result<Vec<u8>, Error> {
+        let mut result = Err(Error::Err(format!("chunk {} of {} not read", index, path)));
+        for _attempt in 0..MAX_RETRIES {
+            let output = self.run_shell_output(&chunk_command(path, index)).await?;
+            result = parse_chunk(&output);
+            match &result {
+                Ok(_) => break,
+                Err(_e) => {
+                    debug!("chunk {} of {} attempt {}: {}", index, path, _attempt, _e);
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cksum() {
+        assert_eq!(cksum(b""), 4294967295);
+        assert_eq!(cksum(b"a"), 1220704766);
+        assert_eq!(cksum(b"123456789"), 930766865);
+    }
+
+    #[test]
+    fn test_base64_decode() {
+        assert_eq!(
+            base64_decode("aGVsbG8g\nd29ybGQ=").unwrap(),
+            b"hello world".to_vec()
+        );
+        assert_eq!(base64_decode("YQ==").unwrap(), b"a".to_vec());
+        assert_eq!(base64_decode("").unwrap(), Vec::<u8>::new());
+        assert!(base64_decode("YQ=").is_err());
+        assert!(base64_decode("Y!==").is_err());
+    }
+
+    #[test]
+    fn test_parse_chunk() {
+        assert_eq!(parse_chunk("1220704766 1\nYQ==").unwrap(), b"a".to_vec());
+        assert!(parse_chunk("1220704766 1\nYg==").is_err());
+        assert!(parse_chunk("1220704766 2\nYQ==").is_err());
+        assert!(parse_chunk("sh: dd: not found").is_err());
+        let command = chunk_command("/tmp/a.png", 2);
+        assert!(command.starts_with("dd if='/tmp/a.png' of='/tmp/a.png.chunk' bs=49152 skip=2"));
+        let command = chunk_command("/tmp/it's.png", 0);
+        assert!(command.starts_with("dd if='/tmp/it'\\''s.png' of='/tmp/it'\\''s.png.chunk'"));
+    }
+}
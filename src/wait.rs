@@ -0,0 +1,381 @@
+use std::time::{Duration, Instant};
+
+use crate::entity::{FindBuilder, MatchInfo};
+use crate::error::Error;
+use crate::geometry::{Point, Rect};
+use crate::operation::{traced, OperationId};
+use crate::screenshot::Screenshot;
+use crate::zx_touch::ZxTouch;
+
+/// 轮询等待参数,时间单位均为毫秒
+#[derive(Debug, Clone)]
+pub struct WaitBuilder {
+    pub timeout: u32,
+    /// 首次轮询间隔
+    pub interval: u32,
+    /// 每次轮询后间隔乘以该系数,1.0 表示固定间隔
+    pub backoff: f32,
+    pub max_interval: u32,
+    /// 检测屏幕变化时的采样间隔(像素)
+    pub step: u32,
+    /// 检测屏幕变化时,采样点各通道差值不超过该值视为没有变化
+    pub color_tolerance: u8,
+    /// 变化的采样点超过该比例才算画面变化,闪烁的光标、时钟等小面积变化不计
+    pub change_ratio: f64,
+}
+
+unsafe impl Send for WaitBuilder {}
+unsafe impl Sync for WaitBuilder {}
+
+impl WaitBuilder {
+    pub fn new() -> Self {
+        Self {
+            timeout: 3000,
+            interval: 100,
+            backoff: 1.5,
+            max_interval: 1000,
+            step: 20,
+            color_tolerance: 16,
+            change_ratio: 0.01,
+        }
+    }
+    pub fn timeout(&mut self, timeout: u32) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+    pub fn interval(&mut self, interval: u32) -> &mut Self {
+        self.interval = interval;
+        self
+    }
+    pub fn backoff(&mut self, backoff: f32) -> &mut Self {
+        self.backoff = backoff;
+        self
+    }
+    pub fn max_interval(&mut self, max_interval: u32) -> &mut Self {
+        self.max_interval = max_interval;
+        self
+    }
+    pub fn step(&mut self, step: u32) -> &mut Self {
+        self.step = step;
+        self
+    }
+    pub fn color_tolerance(&mut self, color_tolerance: u8) -> &mut Self {
+        self.color_tolerance = color_tolerance;
+        self
+    }
+    pub fn change_ratio(&mut self, change_ratio: f64) -> &mut Self {
+        self.change_ratio = change_ratio;
+        self
+    }
+    /// 两次采样之间画面是否发生了变化
+    pub(crate) fn changed(&self, before: &Screenshot, after: &Screenshot) -> bool {
+        before.difference(after, self.color_tolerance) > self.change_ratio
+    }
+
+    pub fn build(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl Default for WaitBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `wait_for_any` 的结果:出现的模板在参数中的下标及其位置
+#[derive(Debug, Clone)]
+pub struct FoundImage {
+    pub index: usize,
+    pub info: MatchInfo,
+}
+
+unsafe impl Send for FoundImage {}
+unsafe impl Sync for FoundImage {}
+
+/// 基于单调时钟的轮询节奏,间隔按 `backoff` 递增并且不会越过截止时间
+pub(crate) struct Poller {
+    deadline: Instant,
+    interval: f64,
+    backoff: f64,
+    max_interval: f64,
+}
+
+impl Poller {
+    pub(crate) fn new(wait: &WaitBuilder) -> Self {
+        Self {
+            deadline: Instant::now() + Duration::from_millis(wait.timeout as u64),
+            interval: wait.interval as f64,
+            backoff: (wait.backoff as f64).max(1.0),
+            max_interval: wait.max_interval.max(wait.interval) as f64,
+        }
+    }
+    /// 下一次等待的时长,已超时返回 `None`
+    fn next_delay(&mut self, now: Instant) -> Option<Duration> {
+        let remaining = self.deadline.checked_duration_since(now)?;
+        if remaining.is_zero() {
+            return None;
+        }
+        let delay = Duration::from_millis(self.interval as u64).min(remaining);
+        self.interval = (self.interval * self.backoff).min(self.max_interval);
+        Some(delay)
+    }
+    /// 等待到下一次轮询,已超时返回 `false`
+    pub(crate) async fn tick(&mut self) -> bool {
+        match self.next_delay(Instant::now()) {
+            Some(delay) => {
+                futures_timer::Delay::new(delay).await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 取色每个点都要一次往返,采样点超过这个数量时截取整屏再裁剪
+const MAX_PICKED_POINTS: usize = 1024;
+
+/// 记录画面最后一次变化的时间。与最后一次变化时的画面比较,缓慢的变化累积起来也能发现
+pub(crate) struct StabilityTracker {
+    screenshot: Screenshot,
+    changed_at: Instant,
+    quiet_period: Duration,
+}
+
+impl StabilityTracker {
+    pub(crate) fn new(screenshot: Screenshot, now: Instant, quiet_period: u32) -> Self {
+        Self {
+            screenshot,
+            changed_at: now,
+            quiet_period: Duration::from_millis(quiet_period as u64),
+        }
+    }
+    /// 记录新的画面,画面在 `quiet_period` 内没有变化时返回 `true`
+    pub(crate) fn update(
+        &mut self,
+        wait: &WaitBuilder,
+        screenshot: Screenshot,
+        now: Instant,
+    ) -> bool {
+        if wait.changed(&self.screenshot, &screenshot) {
+            self.screenshot = screenshot;
+            self.changed_at = now;
+        }
+        now.duration_since(self.changed_at) >= self.quiet_period
+    }
+}
+
+impl ZxTouch {
+    /// 按 `step` 采样当前画面,`region` 为 `None` 时取整个屏幕。
+    /// 采样点不多时只对区域内的采样点取色,不截图也不传输整屏图片
+    async fn sample_screen(&self, region: Option<Rect>, step: u32) -> Result<Screenshot, Error> {
+        let screen = Rect::from_origin_size(Point::default(), self.device_screen_size().await?);
+        let rect = match region {
+            Some(region) => self.to_device_rect(region).await?,
+            None => screen,
+        };
+        let rect = screen
+            .intersect(&rect)
+            .unwrap_or_else(|| Rect::from_origin_size(rect.origin(), Default::default()));
+        let (width, height, points) = Screenshot::sample_points(rect, step);
+        if points.len() > MAX_PICKED_POINTS {
+            return self.device_screenshot(rect, step).await;
+        }
+        Ok(Screenshot {
+            rect,
+            step: step.max(1),
+            width,
+            height,
+            pixels: self.device_pick_colors(&points).await?,
+        })
+    }
+    /// 等待画面在 `quiet_period` 毫秒内不再变化,超时返回 `false`
+    pub async fn wait_for_stable_screen(
+        &self,
+        region: Option<Rect>,
+        quiet_period: u32,
+        wait: WaitBuilder,
+    ) -> Result<bool, Error> {
+        let mut poller = Poller::new(&wait);
+        let screenshot = self.sample_screen(region, wait.step).await?;
+        let mut tracker = StabilityTracker::new(screenshot, Instant::now(), quiet_period);
+        while poller.tick().await {
+            let screenshot = self.sample_screen(region, wait.step).await?;
+            if tracker.update(&wait, screenshot, Instant::now()) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    /// 等待画面发生变化,超时返回 `false`
+    pub async fn wait_for_change(
+        &self,
+        region: Option<Rect>,
+        wait: WaitBuilder,
+    ) -> Result<bool, Error> {
+        let mut poller = Poller::new(&wait);
+        let before = self.sample_screen(region, wait.step).await?;
+        while poller.tick().await {
+            let after = self.sample_screen(region, wait.step).await?;
+            if wait.changed(&before, &after) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    /// 等待图像出现,超时返回 `None`
+    pub async fn wait_for_image(
+        &self,
+        image_path: &str,
+        find_builder: FindBuilder,
+        wait: WaitBuilder,
+    ) -> Result<Option<MatchInfo>, Error> {
+        Ok(self
+            .wait_for_any(&[image_path], find_builder, wait)
+            .await?
+            .map(|found| found.info))
+    }
+    /// 等待图像消失,超时返回 `false`
+    pub async fn wait_until_gone(
+        &self,
+        image_path: &str,
+        find_builder: FindBuilder,
+        wait: WaitBuilder,
+    ) -> Result<bool, Error> {
+        let mut poller = Poller::new(&wait);
+        loop {
+            if self
+                .locate_image(image_path, &find_builder)
+                .await?
+                .0
+                .is_none()
+            {
+                return Ok(true);
+            }
+            if !poller.tick().await {
+                return Ok(false);
+            }
+        }
+    }
+    /// 等待任意一个图像出现,按参数顺序查找,超时返回 `None`
+    pub async fn wait_for_any(
+        &self,
+        image_paths: &[&str],
+        find_builder: FindBuilder,
+        wait: WaitBuilder,
+    ) -> Result<Option<FoundImage>, Error> {
+        let id = OperationId::next();
+        traced(
+            id,
+            "wait_for_any",
+            self.wait_any(id, image_paths, find_builder, wait),
+        )
+        .await
+    }
+    /// `wait_for_any` 的实现,调试截图使用调用方的操作编号。
+    /// 超时时为每个模板保存一张调试截图,标注该模板最后一次查找的候选框
+    pub(crate) async fn wait_any(
+        &self,
+        _id: OperationId,
+        image_paths: &[&str],
+        find_builder: FindBuilder,
+        wait: WaitBuilder,
+    ) -> Result<Option<FoundImage>, Error> {
+        let mut poller = Poller::new(&wait);
+        let mut candidates: Vec<Option<MatchInfo>> = vec![None; image_paths.len()];
+        loop {
+            for (index, image_path) in image_paths.iter().enumerate() {
+                match self.locate_image(image_path, &find_builder).await? {
+                    (Some(info), _) => return Ok(Some(FoundImage { index, info })),
+                    (None, candidate) => candidates[index] = candidate,
+                }
+            }
+            if !poller.tick().await {
+                #[cfg(feature = "image")]
+                {
+                    let misses: Vec<(&str, Option<MatchInfo>)> =
+                        image_paths.iter().copied().zip(candidates).collect();
+                    self.save_miss_artifacts(_id, &find_builder, &misses).await;
+                }
+                return Ok(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Color;
+
+    #[test]
+    fn test_poller_backoff() {
+        let wait = WaitBuilder::new()
+            .timeout(1000)
+            .interval(100)
+            .backoff(2.0)
+            .max_interval(300)
+            .build();
+        let mut poller = Poller::new(&wait);
+        let start = poller.deadline - Duration::from_millis(1000);
+        let delays: Vec<u128> = (0..4)
+            .map(|_| poller.next_delay(start).unwrap().as_millis())
+            .collect();
+        assert_eq!(delays, vec![100, 200, 300, 300]);
+        let almost = poller.deadline - Duration::from_millis(50);
+        assert_eq!(poller.next_delay(almost), Some(Duration::from_millis(50)));
+        assert_eq!(poller.next_delay(poller.deadline), None);
+    }
+
+    /// 10×10 个采样点,前 `changed` 个为白色
+    fn frame(changed: usize) -> Screenshot {
+        let mut pixels = vec![Color::new(0, 0, 0); 100];
+        pixels[..changed].fill(Color::new(255, 255, 255));
+        Screenshot {
+            rect: Rect::new(0, 0, 100, 100),
+            step: 10,
+            width: 10,
+            height: 10,
+            pixels,
+        }
+    }
+
+    #[test]
+    fn test_stability_tracker() {
+        let wait = WaitBuilder::new();
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let mut tracker = StabilityTracker::new(frame(0), start, 300);
+        assert!(!tracker.update(&wait, frame(1), at(200)));
+        assert!(tracker.update(&wait, frame(0), at(300)));
+        assert!(!tracker.update(&wait, frame(10), at(350)));
+        assert!(!tracker.update(&wait, frame(10), at(600)));
+        assert!(tracker.update(&wait, frame(11), at(650)));
+        assert!(!tracker.update(&wait, frame(30), at(700)));
+    }
+
+    #[test]
+    fn test_change_tolerance() {
+        let wait = WaitBuilder::new();
+        assert!(!wait.changed(&frame(0), &frame(1)));
+        assert!(wait.changed(&frame(0), &frame(2)));
+        let mut faded = frame(0);
+        faded.pixels.fill(Color::new(10, 10, 10));
+        assert!(!wait.changed(&frame(0), &faded));
+        let strict = WaitBuilder::new()
+            .color_tolerance(0)
+            .change_ratio(0.0)
+            .build();
+        assert!(strict.changed(&frame(0), &frame(1)));
+        assert!(strict.changed(&frame(0), &faded));
+    }
+
+    #[test]
+    fn test_poller_without_backoff() {
+        let wait = WaitBuilder::new().interval(200).backoff(0.5).build();
+        let mut poller = Poller::new(&wait);
+        let start = poller.deadline - Duration::from_millis(3000);
+        assert_eq!(poller.next_delay(start), Some(Duration::from_millis(200)));
+        assert_eq!(poller.next_delay(start), Some(Duration::from_millis(200)));
+    }
+}
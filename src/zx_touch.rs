@@ -1,11 +1,22 @@
+#[cfg(feature = "image")]
+use crate::artifacts::DebugArtifacts;
+use crate::coordinate::CoordinateSpace;
 use crate::entity::{
-    DeviceInfo, FindBuilder, MatchInfo, ParamType, ScreenOrientation, TouchBuilder, TouchFinger,
-    TouchType,
+    Color, DeviceInfo, FindBuilder, MatchInfo, ParamType, ScreenOrientation, SwipeBuilder,
+    TouchBuilder, TouchFinger, TouchType,
 };
 use crate::error::Error;
+use crate::finger::FingerTable;
+use crate::geometry::{Point, Rect, Size};
+use crate::gesture::swipe_path;
+use crate::humanize::Humanizer;
+use crate::operation::{traced, OperationId};
 use crate::r#type::MessageType;
+use crate::template::TemplateRegistry;
+use crate::wait::{FoundImage, WaitBuilder};
 use crate::{debug, error};
 use futures::lock::Mutex;
+use std::collections::HashSet;
 use std::io::{Read, Write};
 use std::net::{SocketAddr, TcpStream};
 use std::sync::Arc;
@@ -13,7 +24,118 @@ use std::sync::Arc;
 pub struct ZxTouch {
     host: String,
     port: i32,
+    /// 连接锁,持有期间不能 `.await`,见 `release_blocking`
     stream: Option<Arc<Mutex<TcpStream>>>,
+    pub(crate) fingers: std::sync::Mutex<FingerTable>,
+    screen_size: std::sync::Mutex<Option<Size>>,
+    screen_scale: std::sync::Mutex<Option<i32>>,
+    screen_orientation: std::sync::Mutex<Option<ScreenOrientation>>,
+    coordinate_space: CoordinateSpace,
+    orientation_aware: bool,
+    pub(crate) uploaded_templates: std::sync::Mutex<HashSet<String>>,
+    template_registry: TemplateRegistry,
+    pub(crate) humanizer: std::sync::Mutex<Option<Humanizer>>,
+    capture_command: Option<String>,
+    #[cfg(feature = "image")]
+    pub(crate) debug_artifacts: Option<DebugArtifacts>,
+    /// 解码后的模板图片,按模板名或设备路径缓存
+    #[cfg(feature = "image")]
+    pub(crate) decoded_templates:
+        std::sync::Mutex<std::collections::HashMap<String, Arc<image::RgbImage>>>,
+}
+
+/// 解析屏幕方向的返回值,格式为 `0;;<UIInterfaceOrientation>`
+fn parse_orientation(msg: &str) -> Result<ScreenOrientation, Error> {
+    msg.split(";;")
+        .nth(1)
+        .and_then(|x| x.trim().parse::<i32>().ok())
+        .map(ScreenOrientation::from)
+        .ok_or_else(|| Error::Err(format!("invalid screen orientation: {}", msg.trim())))
+}
+
+/// 解析屏幕大小的返回值,格式为 `0;;width;;height`
+fn parse_screen_size(msg: &str) -> Result<Size, Error> {
+    let values = msg
+        .trim()
+        .split(";;")
+        .skip(1)
+        .map(|x| x.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>();
+    match values.as_deref() {
+        Some(&[width, height]) if msg.starts_with('0') => Ok(Size::new(width, height)),
+        _ => Err(Error::Err(format!("invalid screen size: {}", msg.trim()))),
+    }
+}
+
+/// 解析屏幕缩放比例的返回值,格式为 `0;;scale`,小数部分舍去
+fn parse_screen_scale(msg: &str) -> Result<i32, Error> {
+    msg.split(";;")
+        .nth(1)
+        .filter(|_| msg.starts_with('0'))
+        .and_then(|x| x.trim().split('.').next())
+        .and_then(|x| x.parse::<i32>().ok())
+        .ok_or_else(|| Error::Err(format!("invalid screen scale: {}", msg.trim())))
+}
+
+/// 解析 shell 命令的返回值,格式为 `0;;<输出>`
+fn parse_shell_output(msg: &str) -> Result<String, Error> {
+    match msg.split_once(";;") {
+        Some(("0", output)) => Ok(output.to_string()),
+        _ => Err(Error::Err(format!("shell command failed: {:.128}", msg))),
+    }
+}
+
+/// 解析取色的返回值,格式为 `0;;r;;g;;b`
+fn parse_color(msg: &str) -> Result<Color, Error> {
+    let channels = msg
+        .trim()
+        .split(";;")
+        .skip(1)
+        .map(|x| x.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>();
+    match channels.as_deref() {
+        Some(&[r, g, b]) if msg.starts_with('0') => Ok(Color::new(
+            r.round().clamp(0.0, 255.0) as u8,
+            g.round().clamp(0.0, 255.0) as u8,
+            b.round().clamp(0.0, 255.0) as u8,
+        )),
+        _ => Err(Error::Err(format!("invalid color: {}", msg.trim()))),
+    }
+}
+
+/// 一批触摸事件的完整消息
+pub(crate) fn perform_touch_message(list: &[(TouchType, Point, TouchFinger)]) -> String {
+    let message_type: u8 = MessageType::PerformTouch.into();
+    let args: Vec<ParamType> = list
+        .iter()
+        .map(|&(touch_type, point, finger)| touch_event_message(touch_type, point, finger).into())
+        .collect();
+    let args_len = args.len();
+    let args_str = args
+        .into_iter()
+        .map(|x| x.into())
+        .collect::<Vec<String>>()
+        .join("");
+    format!("{}{}{}\r\n", message_type, args_len, args_str)
+}
+
+/// 设备端睡眠的完整消息
+pub(crate) fn usleep_message(millseconds: u32) -> String {
+    let message_type: u8 = MessageType::Usleep.into();
+    format!("{}{}\r\n", message_type, millseconds as u64 * 1000)
+}
+
+/// 坐标按 0.1 精度编码
+fn touch_event_message(touch_type: TouchType, point: Point, finger: TouchFinger) -> String {
+    let touch_type: u8 = touch_type.into();
+    let finger: u8 = finger.into();
+    format!(
+        "{}{:02}{:05}{:05}",
+        touch_type,
+        finger,
+        (point.x * 10.0).round() as u32,
+        (point.y * 10.0).round() as u32
+    )
 }
 
 impl ZxTouch {
@@ -22,16 +144,70 @@ impl ZxTouch {
             host: host.as_ref().to_string(),
             port: port.into(),
             stream: None,
+            fingers: std::sync::Mutex::new(FingerTable::default()),
+            screen_size: std::sync::Mutex::new(None),
+            screen_scale: std::sync::Mutex::new(None),
+            screen_orientation: std::sync::Mutex::new(None),
+            coordinate_space: CoordinateSpace::Pixel,
+            orientation_aware: false,
+            uploaded_templates: std::sync::Mutex::new(HashSet::new()),
+            template_registry: TemplateRegistry::new(),
+            humanizer: std::sync::Mutex::new(None),
+            capture_command: None,
+            #[cfg(feature = "image")]
+            debug_artifacts: None,
+            #[cfg(feature = "image")]
+            decoded_templates: std::sync::Mutex::new(std::collections::HashMap::new()),
         }
     }
+    /// 设置坐标系,之后所有触摸和查找接口都使用该坐标系
+    pub fn set_coordinate_space(&mut self, coordinate_space: CoordinateSpace) {
+        self.coordinate_space = coordinate_space;
+    }
+    pub fn coordinate_space(&self) -> CoordinateSpace {
+        self.coordinate_space
+    }
+    /// 开启后坐标按屏幕当前的显示方向解释,发送前自动转换成竖屏坐标
+    pub fn set_orientation_aware(&mut self, orientation_aware: bool) {
+        self.orientation_aware = orientation_aware;
+    }
+    pub fn orientation_aware(&self) -> bool {
+        self.orientation_aware
+    }
+    /// 设置模板库,之后查找接口可以直接使用模板名
+    pub fn set_template_registry(&mut self, template_registry: TemplateRegistry) {
+        self.template_registry = template_registry;
+    }
+    pub fn template_registry(&self) -> &TemplateRegistry {
+        &self.template_registry
+    }
+    /// 设置设备上的截图命令,`{path}` 会替换为 png 的输出路径,例如 `mytool -o {path}`。
+    /// 插件没有截图指令,需要设备上装有能把全屏截图保存为 png 的命令行工具
+    pub fn set_capture_command(&mut self, capture_command: impl Into<String>) {
+        self.capture_command = Some(capture_command.into());
+    }
+    pub fn capture_command(&self) -> Option<&str> {
+        self.capture_command.as_deref()
+    }
+    pub(crate) fn cached_screen_size(&self) -> Option<Size> {
+        *self.screen_size.lock().unwrap()
+    }
+    pub(crate) fn cached_screen_scale(&self) -> Option<i32> {
+        *self.screen_scale.lock().unwrap()
+    }
+    pub(crate) fn cached_screen_orientation(&self) -> Option<ScreenOrientation> {
+        *self.screen_orientation.lock().unwrap()
+    }
     pub async fn close(&mut self) -> Result<(), Error> {
+        let released = self.release_all().await;
         match self.stream.take() {
-            None => Ok(()),
+            None => released,
             Some(socket) => {
                 let socket = socket.lock().await;
-                socket
+                let shutdown = socket
                     .shutdown(std::net::Shutdown::Both)
-                    .map_err(|e| Error::SocketError(e))
+                    .map_err(Error::SocketError);
+                released.and(shutdown)
             }
         }
     }
@@ -39,31 +215,38 @@ impl ZxTouch {
         let addr: SocketAddr = format!("{}:{}", self.host, self.port).parse().unwrap();
         let stream = TcpStream::connect(addr).unwrap();
         self.stream = Some(Arc::new(Mutex::new(stream)));
+        *self.screen_size.lock().unwrap() = None;
+        *self.screen_scale.lock().unwrap() = None;
+        *self.screen_orientation.lock().unwrap() = None;
+        *self.fingers.lock().unwrap() = FingerTable::default();
+        self.uploaded_templates.lock().unwrap().clear();
+        #[cfg(feature = "image")]
+        self.decoded_templates.lock().unwrap().clear();
         Ok(())
     }
     pub(crate) async fn basetouch(
         &self,
         touch_type: TouchType,
-        x: u32,
-        y: u32,
+        point: Point,
         finger: TouchFinger,
     ) -> Result<(), Error> {
         self.connected_required()?;
+        let screen_size = self.device_screen_size().await?;
         let mut socket = self.stream.as_ref().unwrap().lock().await;
+        self.validate_touch(&[(touch_type, point, finger)], screen_size)?;
         let message_type: u8 = MessageType::PerformTouch.into();
-        let touch_type: u8 = touch_type.into();
-        let finger: u8 = finger.into();
         let msg = format!(
-            "{}1{}{:02}{:05}{:05}\r\n",
+            "{}1{}\r\n",
             message_type,
-            touch_type,
-            finger,
-            x * 10,
-            y * 10
+            touch_event_message(touch_type, point, finger)
         );
         match socket.write_all(msg.as_bytes()) {
             Ok(_) => {
                 debug!("send message: {}", msg);
+                self.fingers
+                    .lock()
+                    .unwrap()
+                    .record(touch_type, point, finger);
                 Ok(())
             }
             Err(e) => {
@@ -72,6 +255,102 @@ impl ZxTouch {
             }
         }
     }
+    /// 设备竖屏像素下的屏幕大小,每个连接只向设备查询一次
+    pub(crate) async fn device_screen_size(&self) -> Result<Size, Error> {
+        match self.cached_screen_size() {
+            Some(size) => Ok(size),
+            None => self.get_screen_size().await,
+        }
+    }
+    /// 发送前检查触摸事件顺序和坐标是否在屏幕内
+    pub(crate) fn validate_touch(
+        &self,
+        events: &[(TouchType, Point, TouchFinger)],
+        screen_size: Size,
+    ) -> Result<(), Error> {
+        let result = self
+            .fingers
+            .lock()
+            .unwrap()
+            .validate(events, Some(screen_size));
+        if let Err(_e) = &result {
+            error!("{}", _e);
+        }
+        result
+    }
+    /// 同步抬起手指,供 `FingerGuard` 在 drop 时使用。失败时只记录日志,并且不再视为按下,避免该手指永远无法分配
+    pub(crate) fn release_blocking(&self, finger: TouchFinger) {
+        let Some(point) = self.fingers.lock().unwrap().position(finger) else {
+            return;
+        };
+        let Some(stream) = self.stream.as_ref() else {
+            self.fingers
+                .lock()
+                .unwrap()
+                .record(TouchType::Up, point, finger);
+            return;
+        };
+        let message_type: u8 = MessageType::PerformTouch.into();
+        let msg = format!(
+            "{}1{}\r\n",
+            message_type,
+            touch_event_message(TouchType::Up, point, finger)
+        );
+        // 在 drop 中同步等待连接锁。所有持有连接锁的代码都只做阻塞读写,不会在持有锁时 `.await`,
+        // 所以锁的持有者不会让出执行权等待本线程,这里最多等到它的读写完成,不会死锁。
+        // 修改任何持有连接锁的代码时都要保持这一点
+        let mut socket = futures::executor::block_on(stream.lock());
+        match socket.write_all(msg.as_bytes()) {
+            Ok(_) => {
+                debug!("send message: {}", msg);
+                self.fingers
+                    .lock()
+                    .unwrap()
+                    .record(TouchType::Up, point, finger);
+            }
+            Err(_e) => {
+                error!("write error: {}", _e);
+                self.fingers
+                    .lock()
+                    .unwrap()
+                    .record(TouchType::Up, point, finger);
+            }
+        }
+    }
+    /// 一次写入多条消息,设备按顺序执行,读到 `responses` 条以 `\r\n` 结尾的返回后结束。期间一直占用连接
+    pub(crate) async fn send_pipelined(
+        &self,
+        msg: &str,
+        responses: usize,
+        touches: &[(TouchType, Point, TouchFinger)],
+    ) -> Result<(), Error> {
+        self.connected_required()?;
+        let mut socket = self.stream.as_ref().unwrap().lock().await;
+        match socket.write_all(msg.as_bytes()) {
+            Ok(_) => {
+                debug!("send message: {}", msg);
+                let mut fingers = self.fingers.lock().unwrap();
+                for &(touch_type, point, finger) in touches {
+                    fingers.record(touch_type, point, finger);
+                }
+            }
+            Err(e) => {
+                error!("write error: {}", e);
+                return Err(Error::SocketError(e));
+            }
+        }
+        let mut received = String::new();
+        while received.matches("\r\n").count() < responses {
+            let mut buffer = [0u8; 1024];
+            let size = socket.read(&mut buffer).map_err(Error::SocketError)?;
+            if size == 0 {
+                return Err(Error::SocketError(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            received.push_str(&String::from_utf8_lossy(&buffer[..size]));
+        }
+        debug!("Received message: {}", received);
+        Ok(())
+    }
     fn connected_required(&self) -> Result<(), Error> {
         if self.stream.is_none() {
             error!("not connected");
@@ -126,80 +405,135 @@ impl ZxTouch {
             .map_err(|e| Error::SocketError(e))?
     }
     /// 点下屏幕坐标
-    pub async fn touch_down(&self, x: u32, y: u32, finger: TouchFinger) -> Result<(), Error> {
-        debug!("touch down: {} {} {:?}", x, y, finger);
-        self.basetouch(TouchType::Down, x, y, finger).await
+    pub async fn touch_down(
+        &self,
+        point: impl Into<Point>,
+        finger: TouchFinger,
+    ) -> Result<(), Error> {
+        let point = self.to_device(point.into()).await?;
+        debug!("touch down: {:?} {:?}", point, finger);
+        self.basetouch(TouchType::Down, point, finger).await
+    }
+    /// 点击屏幕坐标,开启拟人化时点在坐标附近并随机按住一段时间
+    pub async fn touch(&self, point: impl Into<Point>) -> Result<(), Error> {
+        let point = self.humanize_near(point.into()).await?;
+        self.tap(point).await
     }
-    /// 点击屏幕坐标
-    pub async fn touch(&self, x: u32, y: u32) -> Result<(), Error> {
-        self.touch_down(x, y, TouchFinger::Five).await?;
-        self.touch_up(x, y, TouchFinger::Five).await
+    /// 点击区域,开启拟人化时按正态分布落在区域内,否则点击中心
+    pub async fn touch_rect(&self, rect: Rect) -> Result<Point, Error> {
+        let point = self
+            .humanize(|humanizer| humanizer.point_in(rect))
+            .unwrap_or_else(|| rect.center());
+        self.tap(point).await?;
+        Ok(point)
+    }
+    async fn tap(&self, point: Point) -> Result<(), Error> {
+        let press = self.humanize(|humanizer| humanizer.press_duration());
+        let finger = self.allocate_finger()?;
+        finger.down(point).await?;
+        if let Some(press) = press {
+            self.delay(press).await;
+        }
+        finger.up().await
     }
     /// 长按屏幕坐标
-    pub async fn touch_long(&self, x: u32, y: u32, duration: u32) -> Result<(), Error> {
-        self.touch_down(x, y, TouchFinger::Five).await?;
-        self.sleep(duration).await?;
-        self.touch_up(x, y, TouchFinger::Five).await
+    pub async fn touch_long(&self, point: impl Into<Point>, duration: u32) -> Result<(), Error> {
+        let finger = self.allocate_finger()?;
+        finger.down(point).await?;
+        self.delay(duration).await;
+        finger.up().await
     }
 
     /// 滑动屏幕坐标
     pub async fn swipe(
         &self,
-        x: u32,
-        y: u32,
-        to_x: u32,
-        to_y: u32,
+        from: impl Into<Point>,
+        to: impl Into<Point>,
         duration: u32,
     ) -> Result<(), Error> {
-        self.touch_down(x, y, TouchFinger::Five).await?;
-        if (to_x - x > 50) || (to_y - y > 50) {
-            self.sleep(duration / 2).await?;
-            self.touch_move(x + (to_x - x) / 2, y + (to_y - y) / 2, TouchFinger::Five)
-                .await?; //过渡
-            self.sleep(duration / 2).await?;
-        } else {
-            self.sleep(duration).await?;
-            self.touch_move(to_x, to_y, TouchFinger::Five).await?;
-        }
-        self.touch_up(to_x, to_y, TouchFinger::Five).await
+        let builder = SwipeBuilder::new()
+            .from(from)
+            .to(to)
+            .duration(duration)
+            .build();
+        self.swipe_with(builder).await
+    }
+    /// 按缓动曲线平滑滑动,开启拟人化时起止点、时长和速度带有随机性,轨迹弯成弧线
+    pub async fn swipe_with(&self, mut builder: SwipeBuilder) -> Result<(), Error> {
+        builder.from = self.humanize_near(builder.from).await?;
+        builder.to = self.humanize_near(builder.to).await?;
+        let humanized = self.humanize(|humanizer| {
+            builder.duration = humanizer.duration(builder.duration);
+            let path = swipe_path(&builder);
+            let intervals = humanizer.intervals(builder.duration, path.len() - 1);
+            (path, intervals)
+        });
+        let (path, intervals) = match humanized {
+            Some((path, intervals)) => (self.humanize_curve(&path).await?, intervals),
+            None => {
+                let path = swipe_path(&builder);
+                let interval = builder.duration / (path.len() as u32 - 1);
+                let intervals = vec![interval; path.len() - 1];
+                (path, intervals)
+            }
+        };
+        let finger = self.allocate_finger()?;
+        finger.down(path[0]).await?;
+        for (&point, interval) in path[1..].iter().zip(intervals) {
+            self.delay(interval).await;
+            finger.move_to(point).await?;
+        }
+        finger.up().await
     }
     /// 移动屏幕坐标
-    pub async fn touch_move(&self, x: u32, y: u32, finger: TouchFinger) -> Result<(), Error> {
-        debug!("touch move: {} {} {:?}", x, y, finger);
-        self.basetouch(TouchType::Move, x, y, finger).await
+    pub async fn touch_move(
+        &self,
+        point: impl Into<Point>,
+        finger: TouchFinger,
+    ) -> Result<(), Error> {
+        let point = self.to_device(point.into()).await?;
+        debug!("touch move: {:?} {:?}", point, finger);
+        self.basetouch(TouchType::Move, point, finger).await
     }
     /// 抬起屏幕坐标
-    pub async fn touch_up(&self, x: u32, y: u32, finger: TouchFinger) -> Result<(), Error> {
-        debug!("touch up: {} {} {:?}", x, y, finger);
-        self.basetouch(TouchType::Up, x, y, finger).await
+    pub async fn touch_up(
+        &self,
+        point: impl Into<Point>,
+        finger: TouchFinger,
+    ) -> Result<(), Error> {
+        let point = self.to_device(point.into()).await?;
+        debug!("touch up: {:?} {:?}", point, finger);
+        self.basetouch(TouchType::Up, point, finger).await
     }
     /// 批量点击
-    pub async fn touch_events(
+    pub async fn touch_events<P: Into<Point>>(
         &self,
-        list: Vec<(TouchType, u32, u32, TouchFinger)>,
+        list: Vec<(TouchType, P, TouchFinger)>,
+    ) -> Result<(), Error> {
+        let mut events = Vec::with_capacity(list.len());
+        for (touch_type, point, finger) in list {
+            let point = self.to_device(point.into()).await?;
+            events.push((touch_type, point, finger));
+        }
+        self.send_touch_events(events).await
+    }
+    /// 发送设备像素坐标的批量事件
+    pub(crate) async fn send_touch_events(
+        &self,
+        list: Vec<(TouchType, Point, TouchFinger)>,
     ) -> Result<(), Error> {
         self.connected_required()?;
+        let screen_size = self.device_screen_size().await?;
         let mut socket = self.stream.as_ref().unwrap().lock().await;
-        let message_type: u8 = MessageType::PerformTouch.into();
-        let args: Vec<ParamType> = list
-            .into_iter()
-            .map(|(touch_type, x, y, finger)| {
-                let touch_type: u8 = touch_type.into();
-                let finger: u8 = finger.into();
-                format!("{}{:02}{:05}{:05}", touch_type, finger, x * 10, y * 10).into()
-            })
-            .collect();
-        let args_len = args.len();
-        let args_str = args
-            .into_iter()
-            .map(|x| x.into())
-            .collect::<Vec<String>>()
-            .join("");
-        let args_str = format!("{}{}", args_len, args_str);
-        let msg = format!("{}{}\r\n", message_type, args_str);
+        self.validate_touch(&list, screen_size)?;
+        let msg = perform_touch_message(&list);
         match socket.write_all(msg.as_bytes()) {
             Ok(_) => {
                 debug!("send message: {}", msg);
+                let mut fingers = self.fingers.lock().unwrap();
+                for (touch_type, point, finger) in list {
+                    fingers.record(touch_type, point, finger);
+                }
                 Ok(())
             }
             Err(e) => {
@@ -261,13 +595,74 @@ impl ZxTouch {
             .map_err(|e| Error::SocketError(e))?
     }
 
-    /// 睡眠
+    /// 执行 shell 命令并返回标准输出。
+    ///
+    /// 假定插件把标准输出放在退出码之后,返回 `0;;<输出>`。这一格式没有在插件源码中确认,
+    /// 返回值不是这种格式时报错,不会把整条消息当作输出
+    pub async fn run_shell_output(&self, command: &str) -> Result<String, Error> {
+        let message_type: u8 = MessageType::RunShell.into();
+        let msg = self
+            .request(&format!("{}{}\r\n", message_type, command))
+            .await?;
+        parse_shell_output(&msg)
+    }
+    /// 只发送消息,不读取返回值,用于插件不保证回复的消息
+    #[cfg(feature = "experimental")]
+    pub(crate) async fn send(&self, args: &str) -> Result<(), Error> {
+        self.connected_required()?;
+        let mut socket = self.stream.as_ref().unwrap().lock().await;
+        match socket.write_all(args.as_bytes()) {
+            Ok(_) => {
+                debug!("send message: {}", args);
+                Ok(())
+            }
+            Err(e) => {
+                error!("write error: {}", e);
+                Err(Error::SocketError(e))
+            }
+        }
+    }
+    /// 发送一条消息并读取完整的返回值(去掉结尾的 `\r\n`)。
+    /// 返回值可能超过一次读取的大小,所以一直读到结束符
+    pub(crate) async fn request(&self, args: &str) -> Result<String, Error> {
+        self.connected_required()?;
+        let mut socket = self.stream.as_ref().unwrap().lock().await;
+        match socket.write_all(args.as_bytes()) {
+            Ok(_) => {
+                debug!("send message: {}", args);
+            }
+            Err(e) => {
+                error!("write error: {}", e);
+                return Err(Error::SocketError(e));
+            }
+        }
+        let mut received = Vec::new();
+        while !received.ends_with(b"\r\n") {
+            let mut buffer = [0u8; 4096];
+            let size = socket.read(&mut buffer).map_err(Error::SocketError)?;
+            if size == 0 {
+                return Err(Error::SocketError(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            received.extend_from_slice(&buffer[..size]);
+        }
+        received.truncate(received.len() - 2);
+        let msg = String::from_utf8_lossy(&received).to_string();
+        let _size = msg.len();
+        debug!("Received message: {} bytes", _size);
+        Ok(msg)
+    }
+
+    /// 本地等待,不占用连接,其他任务可以在等待期间继续与设备通信
+    pub async fn delay(&self, millseconds: u32) {
+        debug!("delay: {}", millseconds);
+        futures_timer::Delay::new(std::time::Duration::from_millis(millseconds as u64)).await;
+    }
+    /// 设备端睡眠(usleep),设备返回前一直占用连接,适合在批量发送的序列中使用;只是等待时使用 `delay`
     pub async fn sleep(&self, millseconds: u32) -> Result<String, Error> {
         debug!("sleep: {}", millseconds);
         self.connected_required()?;
         let mut socket = self.stream.as_ref().unwrap().lock().await;
-        let message_type: u8 = MessageType::Usleep.into();
-        let args = format!("{}{}\r\n", message_type, millseconds * 1000);
+        let args = usleep_message(millseconds);
         match socket.write_all(args.as_bytes()) {
             Ok(_) => {
                 debug!("send message: {}", args);
@@ -405,7 +800,7 @@ impl ZxTouch {
     }
 
     ///获取屏幕大小
-    pub async fn get_screen_size(&self) -> Result<(i32, i32), Error> {
+    pub async fn get_screen_size(&self) -> Result<Size, Error> {
         self.connected_required()?;
         let mut socket = self.stream.as_ref().unwrap().lock().await;
         let message_type: u8 = MessageType::GetDeviceInfo.into();
@@ -422,18 +817,14 @@ impl ZxTouch {
         let mut buffer = [0u8; 1024];
         socket
             .read(&mut buffer)
-            .map(|size| {
+            .map_err(Error::SocketError)
+            .and_then(|size| {
                 let msg = String::from_utf8_lossy(&buffer[..size]);
                 debug!("Received message: {}", msg);
-                let result = msg.to_string();
-                let arr = result.split(";;").collect::<Vec<&str>>();
-                (
-                    arr[1].split(".").collect::<Vec<_>>()[0].parse().unwrap(),
-                    arr[2].split(".").collect::<Vec<_>>()[0].parse().unwrap(),
-                )
+                let size = parse_screen_size(&msg)?;
+                *self.screen_size.lock().unwrap() = Some(size);
+                Ok(size)
             })
-            .map(Ok)
-            .map_err(|e| Error::SocketError(e))?
     }
 
     /// 获取屏幕方向
@@ -454,20 +845,14 @@ impl ZxTouch {
         let mut buffer = [0u8; 1024];
         socket
             .read(&mut buffer)
-            .map(|size| {
+            .map_err(Error::SocketError)
+            .and_then(|size| {
                 let msg = String::from_utf8_lossy(&buffer[..size]);
                 debug!("Received message: {}", msg);
-                msg.split(";;")
-                    .collect::<Vec<_>>()
-                    .get(1)
-                    .map(|x| x.trim())
-                    .unwrap()
-                    .parse::<i32>()
-                    .unwrap()
-                    .into()
+                let orientation = parse_orientation(&msg)?;
+                *self.screen_orientation.lock().unwrap() = Some(orientation);
+                Ok(orientation)
             })
-            .map(Ok)
-            .map_err(|e| Error::SocketError(e))?
     }
     /// 获取屏幕缩放比例
     pub async fn get_screen_scale(&self) -> Result<i32, Error> {
@@ -487,21 +872,51 @@ impl ZxTouch {
         let mut buffer = [0u8; 1024];
         socket
             .read(&mut buffer)
-            .map(|size| {
+            .map_err(Error::SocketError)
+            .and_then(|size| {
                 let msg = String::from_utf8_lossy(&buffer[..size]);
                 debug!("Received message: {}", msg);
-                msg.split(";;")
-                    .collect::<Vec<_>>()
-                    .get(1)
-                    .map(|x| x.trim())
-                    .map(|x| x.split(".").collect::<Vec<_>>()[0])
-                    .unwrap()
-                    .parse::<i32>()
-                    .unwrap()
-                    .into()
+                let scale = parse_screen_scale(&msg)?;
+                *self.screen_scale.lock().unwrap() = Some(scale);
+                Ok(scale)
             })
-            .map(Ok)
-            .map_err(|e| Error::SocketError(e))?
+    }
+
+    /// 获取某一点的颜色
+    pub async fn pick_color(&self, point: impl Into<Point>) -> Result<Color, Error> {
+        let point = self.to_device(point.into()).await?;
+        let colors = self.device_pick_colors(&[point]).await?;
+        Ok(colors[0])
+    }
+    /// 批量取色,坐标为设备像素。整批请求期间占用连接,每个返回值都会校验格式
+    pub(crate) async fn device_pick_colors(&self, points: &[Point]) -> Result<Vec<Color>, Error> {
+        self.connected_required()?;
+        let mut socket = self.stream.as_ref().unwrap().lock().await;
+        let message_type: u8 = MessageType::ColorPicker.into();
+        let mut colors = Vec::with_capacity(points.len());
+        for point in points {
+            let args = format!(
+                "{}{};;{}\r\n",
+                message_type,
+                point.x.round() as u32,
+                point.y.round() as u32
+            );
+            match socket.write_all(args.as_bytes()) {
+                Ok(_) => {
+                    debug!("send message: {}", args);
+                }
+                Err(e) => {
+                    error!("write error: {}", e);
+                    return Err(Error::SocketError(e));
+                }
+            }
+            let mut buffer = [0u8; 1024];
+            let size = socket.read(&mut buffer).map_err(Error::SocketError)?;
+            let msg = String::from_utf8_lossy(&buffer[..size]);
+            debug!("Received message: {}", msg);
+            colors.push(parse_color(&msg)?);
+        }
+        Ok(colors)
     }
 
     /// 获取设备信息
@@ -577,29 +992,95 @@ impl ZxTouch {
         touch_builder: TouchBuilder,
     ) -> Result<bool, Error> {
         self.connected_required()?;
-        let expire_time = std::time::SystemTime::now()
-            + std::time::Duration::from_secs(touch_builder.timeout_seconds as u64);
-        loop {
-            if std::time::SystemTime::now() > expire_time {
-                break;
-            }
-            let find_info = self
-                .image_find(image_path, touch_builder.find_builder.clone())
+        let id = OperationId::next();
+        traced(id, "touch_image", async {
+            let wait = WaitBuilder::new()
+                .timeout(touch_builder.timeout_seconds * 1000)
+                .build();
+            let found = self
+                .wait_any(id, &[image_path], touch_builder.find_builder.clone(), wait)
                 .await?;
-            if let Some(find_info) = find_info {
-                let x = find_info.x + find_info.w / 2;
-                let y = find_info.y + find_info.h / 2;
-                self.touch(x, y).await?;
-                return Ok(true);
+            match found {
+                Some(FoundImage {
+                    info: find_info, ..
+                }) => {
+                    let point = self
+                        .humanize(|humanizer| humanizer.point_in(find_info.rect))
+                        .unwrap_or_else(|| find_info.center());
+                    #[cfg(feature = "image")]
+                    self.save_tap_artifact(
+                        id,
+                        image_path,
+                        &touch_builder.find_builder,
+                        &find_info,
+                        point,
+                    )
+                    .await;
+                    self.tap(point).await?;
+                    Ok(true)
+                }
+                None => Ok(false),
             }
-        }
-        Ok(false)
+        })
+        .await
     }
-    /// 图像查找
+    /// 图像查找,`image_path` 可以是设备上的路径或模板库中的模板名。
+    /// 设置了 `region` 时截取该区域在主机上查找,需要开启 `image` feature
     pub async fn image_find(
         &self,
         image_path: &str,
         find_builder: FindBuilder,
+    ) -> Result<Option<MatchInfo>, Error> {
+        let id = OperationId::next();
+        traced(id, "image_find", async {
+            let (find_info, _candidate) = self.locate_image(image_path, &find_builder).await?;
+            #[cfg(feature = "image")]
+            if find_info.is_none() {
+                self.save_miss_artifacts(id, &find_builder, &[(image_path, _candidate)])
+                    .await;
+            }
+            Ok(find_info)
+        })
+        .await
+    }
+    /// 图像查找,不保存调试截图。
+    /// 同时返回最佳候选(设备竖屏像素):在主机上查找时为相似度最高的位置,没找到时它低于阈值;
+    /// 设备端查找只返回达到阈值的结果,没找到时没有候选。
+    /// 设置了查找区域时,开启 `image` feature 只截取该区域在主机上查找;
+    /// 否则在设备上全屏查找,全屏最佳结果的中心不在区域内时视为没有找到
+    pub(crate) async fn locate_image(
+        &self,
+        image_path: &str,
+        find_builder: &FindBuilder,
+    ) -> Result<(Option<MatchInfo>, Option<MatchInfo>), Error> {
+        #[cfg(feature = "image")]
+        if find_builder.region.is_some() {
+            return self.locate_in_region(image_path, find_builder).await;
+        }
+        let image_path = self.resolve_template(image_path).await?;
+        let find_info = self
+            .device_image_find(&image_path, find_builder.clone())
+            .await?;
+        match find_info {
+            Some(candidate) => {
+                let info = MatchInfo {
+                    rect: self.from_device_rect(candidate.rect).await?,
+                    score: candidate.score,
+                };
+                if find_builder.accepts(&info) {
+                    Ok((Some(info), Some(candidate)))
+                } else {
+                    Ok((None, Some(candidate)))
+                }
+            }
+            None => Ok((None, None)),
+        }
+    }
+    /// 图像查找,结果为设备像素坐标
+    pub(crate) async fn device_image_find(
+        &self,
+        image_path: &str,
+        find_builder: FindBuilder,
     ) -> Result<Option<MatchInfo>, Error> {
         self.connected_required()?;
         let mut socket = self.stream.as_ref().unwrap().lock().await;
@@ -631,14 +1112,16 @@ impl ZxTouch {
                 let infos = msg.split(";;").collect::<Vec<&str>>();
                 match infos.as_slice() {
                     &[_, x, y, w, h, ..] => {
-                        let x = x.split(".").collect::<Vec<_>>()[0].parse().unwrap();
-                        let y = y.split(".").collect::<Vec<_>>()[0].parse().unwrap();
-                        let w = w.split(".").collect::<Vec<_>>()[0].parse().unwrap();
-                        let h = h.split(".").collect::<Vec<_>>()[0].parse().unwrap();
-                        if x == 0 && y == 0 && w == 0 && h == 0 {
+                        let rect = Rect::new(
+                            x.trim().parse::<f64>().unwrap(),
+                            y.trim().parse::<f64>().unwrap(),
+                            w.trim().parse::<f64>().unwrap(),
+                            h.trim().parse::<f64>().unwrap(),
+                        );
+                        if rect == Rect::default() {
                             return Ok(None);
                         }
-                        Ok(Some(MatchInfo { x, y, w, h }))
+                        Ok(Some(MatchInfo { rect, score: None }))
                     }
                     _ => Ok(None),
                 }
@@ -649,7 +1132,18 @@ impl ZxTouch {
 
 #[cfg(test)]
 mod tests {
-    use crate::entity::{FindBuilder, TouchBuilder};
+    use super::{
+        parse_color, parse_orientation, parse_screen_scale, parse_screen_size, parse_shell_output,
+    };
+    use crate::clicker::ClickBuilder;
+    use crate::coordinate::CoordinateSpace;
+    use crate::entity::{Color, FindBuilder, ScreenOrientation, SwipeBuilder, TouchBuilder};
+    use crate::geometry::{Rect, Size};
+    use crate::humanize::HumanizeProfile;
+    use crate::scroll::{ScrollDirection, ScrollTarget};
+    use crate::template::TemplateImage;
+    use crate::timeline::Timeline;
+    use crate::wait::WaitBuilder;
     use crate::zx_touch::{TouchFinger, TouchType, ZxTouch};
     use tracing::level_filters::LevelFilter;
 
@@ -682,7 +1176,7 @@ mod tests {
         let mut touch = ZxTouch::new("192.168.3.113", 6000);
         touch.connect().await.unwrap();
         touch
-            .touch_down(329, 2144, TouchFinger::Five)
+            .touch_down((329, 2144), TouchFinger::Five)
             .await
             .unwrap();
         touch.close().await.unwrap();
@@ -695,8 +1189,8 @@ mod tests {
         touch.connect().await.unwrap();
         touch
             .touch_events(vec![
-                (TouchType::Down, 300, 400, TouchFinger::Five),
-                (TouchType::Up, 300, 400, TouchFinger::Five),
+                (TouchType::Down, (300, 400), TouchFinger::Five),
+                (TouchType::Up, (300, 400), TouchFinger::Five),
             ])
             .await
             .unwrap();
@@ -730,6 +1224,14 @@ mod tests {
         touch.close().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_delay_without_connection() {
+        let touch = ZxTouch::new("192.168.3.113", 6000);
+        let start = std::time::Instant::now();
+        touch.delay(50).await;
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+    }
+
     #[tokio::test]
     async fn test_sleep() {
         init_log();
@@ -765,11 +1267,78 @@ mod tests {
         init_log();
         let mut touch = ZxTouch::new("192.168.3.113", 6000);
         touch.connect().await.unwrap();
-        let (width, height) = touch.get_screen_size().await.unwrap();
-        println!("iphone width:{width} , height:{height}");
+        let size = touch.get_screen_size().await.unwrap();
+        println!("iphone width:{} , height:{}", size.width, size.height);
         touch.close().await.unwrap();
     }
 
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(
+            parse_color("0;;255;;128;;0\r\n").unwrap(),
+            Color::new(255, 128, 0)
+        );
+        assert_eq!(
+            parse_color("0;;12.0;;34.4;;56.6").unwrap(),
+            Color::new(12, 34, 57)
+        );
+        assert!(parse_color("-1;;error").is_err());
+        assert!(parse_color("0;;12;;34").is_err());
+    }
+
+    #[test]
+    fn test_parse_shell_output() {
+        assert_eq!(
+            parse_shell_output("0;;4294967295 0\nYQ==").unwrap(),
+            "4294967295 0\nYQ=="
+        );
+        assert_eq!(parse_shell_output("0;;").unwrap(), "");
+        assert!(parse_shell_output("1;;sh: dd: not found").is_err());
+        assert!(parse_shell_output("0").is_err());
+        assert!(parse_shell_output("").is_err());
+    }
+
+    #[test]
+    fn test_parse_screen_scale() {
+        assert_eq!(parse_screen_scale("0;;3.000000\r\n").unwrap(), 3);
+        assert_eq!(parse_screen_scale("0;;2").unwrap(), 2);
+        assert!(parse_screen_scale("").is_err());
+        assert!(parse_screen_scale("-1;;error\r\n").is_err());
+        assert!(parse_screen_scale("0;;\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_screen_size() {
+        assert_eq!(
+            parse_screen_size("0;;1170;;2532\r\n").unwrap(),
+            Size::new(1170, 2532)
+        );
+        assert_eq!(
+            parse_screen_size("0;;828.0;;1792.0").unwrap(),
+            Size::new(828, 1792)
+        );
+        assert!(parse_screen_size("").is_err());
+        assert!(parse_screen_size("-1;;error\r\n").is_err());
+        assert!(parse_screen_size("0;;1170\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_orientation() {
+        let golden = [
+            ("0;;1\r\n", ScreenOrientation::Down),
+            ("0;;2\r\n", ScreenOrientation::Up),
+            ("0;;3\r\n", ScreenOrientation::Right),
+            ("0;;4\r\n", ScreenOrientation::Left),
+            ("0;;0\r\n", ScreenOrientation::Unknown(0)),
+            ("0;;1", ScreenOrientation::Down),
+        ];
+        for (msg, orientation) in golden {
+            assert_eq!(parse_orientation(msg).unwrap(), orientation, "{:?}", msg);
+        }
+        assert!(parse_orientation("").is_err());
+        assert!(parse_orientation("0;;portrait\r\n").is_err());
+    }
+
     #[tokio::test]
     async fn test_get_screen_orientation() {
         init_log();
@@ -813,7 +1382,7 @@ mod tests {
         init_log();
         let mut touch = ZxTouch::new("192.168.3.113", 6000);
         touch.connect().await.unwrap();
-        touch.touch(400, 2250).await.unwrap();
+        touch.touch((400, 2250)).await.unwrap();
         touch.close().await.unwrap();
     }
     #[tokio::test]
@@ -821,7 +1390,7 @@ mod tests {
         init_log();
         let mut touch = ZxTouch::new("192.168.3.113", 6000);
         touch.connect().await.unwrap();
-        touch.touch_long(400, 2247, 500).await.unwrap();
+        touch.touch_long((400, 2247), 500).await.unwrap();
         touch.close().await.unwrap();
     }
     #[tokio::test]
@@ -829,7 +1398,49 @@ mod tests {
         init_log();
         let mut touch = ZxTouch::new("192.168.3.113", 6000);
         touch.connect().await.unwrap();
-        touch.swipe(300, 400, 300, 700, 100).await.unwrap();
+        touch.swipe((300, 400), (300, 700), 100).await.unwrap();
+        touch.close().await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_pinch_out() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        touch.pinch_out((500, 1000), 300, 500).await.unwrap();
+        touch.close().await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_swipe_with() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let builder = SwipeBuilder::new()
+            .from((300, 1500))
+            .to((300, 400))
+            .duration(200)
+            .fling(3000.0)
+            .build();
+        touch.swipe_with(builder).await.unwrap();
+        touch.close().await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_normalized_touch() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.set_coordinate_space(CoordinateSpace::Normalized);
+        touch.connect().await.unwrap();
+        touch.touch((0.5, 0.9)).await.unwrap();
+        touch.close().await.unwrap();
+    }
+    #[tokio::test]
+    async fn test_orientation_aware_touch() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.set_orientation_aware(true);
+        touch.connect().await.unwrap();
+        let orientation = touch.refresh_orientation().await.unwrap();
+        println!("orientation: {:?}", orientation);
+        touch.touch((200, 100)).await.unwrap();
         touch.close().await.unwrap();
     }
     #[tokio::test]
@@ -859,6 +1470,152 @@ mod tests {
         touch.close().await.unwrap();
     }
 
+    #[cfg(feature = "image")]
+    #[tokio::test]
+    async fn test_find_all() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let result = touch
+            .find_all("/var/root/rust/find.jpg", FindBuilder::new())
+            .await
+            .unwrap();
+        println!("result: {:?}", result);
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_any() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let wait = WaitBuilder::new().timeout(5000).interval(200).build();
+        let result = touch
+            .wait_for_any(
+                &["/var/root/rust/find.jpg", "/var/root/rust/close.jpg"],
+                FindBuilder::new(),
+                wait,
+            )
+            .await
+            .unwrap();
+        println!("result: {:?}", result);
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_template() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let template = TemplateImage::file("image/find.jpg");
+        let result = touch
+            .find_template(&template, FindBuilder::new())
+            .await
+            .unwrap();
+        println!("result: {:?}", result);
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_screenshot() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let color = touch.pick_color((100, 100)).await.unwrap();
+        println!("color: {:?}", color);
+        let screenshot = touch.screenshot(30).await.unwrap();
+        println!("screenshot: {}x{}", screenshot.width, screenshot.height);
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_stable_screen() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        touch.touch((400, 2250)).await.unwrap();
+        let wait = WaitBuilder::new().timeout(5000).step(40).build();
+        let stable = touch.wait_for_stable_screen(None, 500, wait).await.unwrap();
+        println!("stable: {}", stable);
+        touch.close().await.unwrap();
+    }
+
+    #[cfg(feature = "experimental")]
+    #[tokio::test]
+    async fn test_crazy_tap() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        touch.crazy_tap_for((400, 400), 20, 2000).await.unwrap();
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_play_timeline() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let timeline = Timeline::new()
+            .tap(0, (400, 400), TouchFinger::One, 50)
+            .tap(250, (600, 400), TouchFinger::One, 50)
+            .tap(500, (400, 400), TouchFinger::One, 50)
+            .build();
+        let report = touch
+            .play_timeline(
+                &timeline,
+                std::time::Instant::now() + std::time::Duration::from_millis(200),
+            )
+            .await
+            .unwrap();
+        println!("report: {:?}", report);
+        touch.play_timeline_on_device(&timeline).await.unwrap();
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_humanize() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.set_humanize_profile(Some(HumanizeProfile::new().seed(42).build()));
+        touch.connect().await.unwrap();
+        touch.touch((400, 400)).await.unwrap();
+        let point = touch
+            .touch_rect(Rect::new(300, 300, 200, 80))
+            .await
+            .unwrap();
+        println!("tap: {:?}", point);
+        touch.swipe((200, 1200), (900, 1200), 500).await.unwrap();
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_click() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let builder = ClickBuilder::new()
+            .point((400, 400))
+            .point((600, 400))
+            .cps(20.0)
+            .count(40)
+            .jitter(5.0)
+            .build();
+        let stats = touch.auto_click(builder).await.unwrap();
+        println!("stats: {:?}", stats);
+        touch.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scroll_until() {
+        init_log();
+        let mut touch = ZxTouch::new("192.168.3.113", 6000);
+        touch.connect().await.unwrap();
+        let target = ScrollTarget::Image("/var/root/rust/find.jpg".to_string(), FindBuilder::new());
+        let result = touch.scroll_until(&target, ScrollDirection::Down, 5).await;
+        println!("result: {:?}", result);
+        touch.close().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_touch_image() {
         init_log();
@@ -880,7 +1637,7 @@ mod tests {
         let touch_builder = TouchBuilder::new();
         touch.connect().await.unwrap();
         // touch.open_app("com.apple.springboard").await.unwrap();
-        touch.touch(400, 2000).await.unwrap();
+        touch.touch((400, 2000)).await.unwrap();
         touch.close().await.unwrap();
     }
 }